@@ -0,0 +1,136 @@
+use crate::game::chess_move::{ChessMove, ParseMoveError};
+use crate::game::game_piece::Piece;
+use crate::game::position::{Position, START_FEN};
+
+#[test]
+fn from_algebraic_rejects_surrounding_text() {
+    let p = Position::from_fen(START_FEN).unwrap();
+    assert_eq!(
+        ChessMove::from_algebraic(" e2e4\n", &p)
+            .unwrap()
+            .to_string(),
+        "e2e4"
+    );
+    for input in ["xxe2e4yy", "xe2e4", "e2e4y", "e2e4 e7e5", "e7e8qq"].iter() {
+        assert!(ChessMove::from_algebraic(input, &p).is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn from_san_parses_piece_and_pawn_moves() {
+    let p = Position::from_fen(START_FEN).unwrap();
+
+    let knight = ChessMove::from_san("Nf3", &p).unwrap();
+    assert_eq!(knight.moved_piece.piece, Piece::Knight);
+    assert_eq!(knight.to_string(), "g1f3");
+
+    let pawn = ChessMove::from_san("e4", &p).unwrap();
+    assert_eq!(pawn.to_string(), "e2e4");
+}
+
+#[test]
+fn from_san_parses_captures_and_castling() {
+    let p =
+        Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+
+    assert_eq!(ChessMove::from_san("dxe6", &p).unwrap().to_string(), "d5e6");
+    assert_eq!(ChessMove::from_san("Bxa6", &p).unwrap().to_string(), "e2a6");
+    assert_eq!(ChessMove::from_san("O-O", &p).unwrap().to_string(), "e1g1");
+    assert_eq!(
+        ChessMove::from_san("O-O-O", &p).unwrap().to_string(),
+        "e1c1"
+    );
+    assert_eq!(
+        ChessMove::from_san("Qxf6+", &p).unwrap().to_string(),
+        "f3f6"
+    );
+}
+
+#[test]
+fn from_san_disambiguates_by_file_and_rank() {
+    let p = Position::from_fen("4k3/8/8/R3N1N1/8/8/8/R3K1N1 w - - 0 1").unwrap();
+
+    assert_eq!(ChessMove::from_san("R1a3", &p).unwrap().to_string(), "a1a3");
+    assert_eq!(ChessMove::from_san("R5a3", &p).unwrap().to_string(), "a5a3");
+    assert_eq!(ChessMove::from_san("Nef3", &p).unwrap().to_string(), "e5f3");
+    assert_eq!(ChessMove::from_san("N1f3", &p).unwrap().to_string(), "g1f3");
+    assert_eq!(
+        ChessMove::from_san("Ng5f3", &p).unwrap().to_string(),
+        "g5f3"
+    );
+
+    match ChessMove::from_san("Ra3", &p) {
+        Err(ParseMoveError::Ambiguous) => (),
+        other => panic!("Expected an ambiguous move but got {:?}", other),
+    }
+}
+
+#[test]
+fn from_san_parses_promotions() {
+    let p = Position::from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+
+    let promotion = ChessMove::from_san("a8=Q+", &p).unwrap();
+    assert_eq!(promotion.promo_piece, Some(Piece::Queen));
+    let capture = ChessMove::from_san("axb8N", &p).unwrap();
+    assert_eq!(capture.to_string(), "a7b8n");
+}
+
+#[test]
+fn from_san_rejects_a_wrong_capture_marker() {
+    let p = Position::from_fen("4k3/8/8/8/8/8/1n6/R3K3 w - - 0 1").unwrap();
+
+    // a8 is empty, so Rxa8 captures nothing.
+    assert_eq!(ChessMove::from_san("Ra8", &p).unwrap().to_string(), "a1a8");
+    match ChessMove::from_san("Rxa8", &p) {
+        Err(ParseMoveError::IllegalMoveError) => (),
+        other => panic!("Expected an illegal move but got {:?}", other),
+    }
+
+    // b2 holds a knight, so moving the bishop there must be written as a capture.
+    let p = Position::from_fen("4k3/8/8/8/8/8/1n6/B3K3 w - - 0 1").unwrap();
+    assert_eq!(ChessMove::from_san("Bxb2", &p).unwrap().to_string(), "a1b2");
+    match ChessMove::from_san("Bb2", &p) {
+        Err(ParseMoveError::IllegalMoveError) => (),
+        other => panic!("Expected an illegal move but got {:?}", other),
+    }
+}
+
+#[test]
+fn from_san_rejects_illegal_moves() {
+    let p = Position::from_fen(START_FEN).unwrap();
+
+    match ChessMove::from_san("Nf6", &p) {
+        Err(ParseMoveError::IllegalMoveError) => (),
+        other => panic!("Expected an illegal move but got {:?}", other),
+    }
+    match ChessMove::from_san("Zz9", &p) {
+        Err(ParseMoveError::PieceParseError) => (),
+        other => panic!("Expected a parse error but got {:?}", other),
+    }
+}
+
+#[test]
+fn to_san_writes_standard_algebraic_notation() {
+    let p =
+        Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    for san in ["dxe6", "Bxa6", "O-O", "O-O-O", "Nxf7", "Qxf6", "a3", "Nb1"].iter() {
+        assert_eq!(ChessMove::from_san(san, &p).unwrap().to_san(&p), *san);
+    }
+
+    let p = Position::from_fen("4k3/8/8/R3N1N1/8/8/8/R3K1N1 w - - 0 1").unwrap();
+    for san in ["R1a3", "R5a3", "Nef3", "N1f3", "Ng5f3", "Ng6", "Ra8+"].iter() {
+        assert_eq!(ChessMove::from_san(san, &p).unwrap().to_san(&p), *san);
+    }
+
+    let p = Position::from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(ChessMove::from_san("a8=Q", &p).unwrap().to_san(&p), "a8=Q");
+}
+
+#[test]
+fn to_san_marks_checkmate() {
+    let p = Position::from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+
+    assert_eq!(ChessMove::from_san("Ra8", &p).unwrap().to_san(&p), "Ra8#");
+}
@@ -1,13 +1,17 @@
+#[cfg(test)]
+mod chess_move_tests;
+mod san;
+
+use super::color::Color;
 use super::game_piece::{GamePiece, Piece};
-use super::position::Position;
+use super::position::{Position, Square};
 use regex::Regex;
 use std::fmt;
 use std::fmt::Display;
-use std::num::ParseIntError;
 use std::str::FromStr;
 
 const ALGEBRAIC_REGEX: &str =
-    "(?P<file1>[a-h])(?P<rank1>[1-8])(?P<file2>[a-h])(?P<rank2>[1-8])(?P<promotionPiece>[bnrq])?";
+    "^(?P<file1>[a-h])(?P<rank1>[1-8])(?P<file2>[a-h])(?P<rank2>[1-8])(?P<promotionPiece>[bnrq])?$";
 
 // Move represents a move on the chess board. It encompasses a piece, the old square and the new square.
 #[derive(Debug, Copy, Clone)]
@@ -41,8 +45,23 @@ impl ChessMove {
             captured_piece,
         }
     }
+    // Parses a move in long algebraic notation, e.g. e2e4 or e7e8q. Castling may be given either as
+    // the king's move, e.g. e1g1, or as O-O and O-O-O for the side to move.
     pub fn from_algebraic(alg_move: &str, p: &Position) -> Result<ChessMove, ParseMoveError> {
         let sanitized = &alg_move.trim().to_lowercase();
+        if let Some(king_file) = parse_castle(sanitized) {
+            let color = p.side_to_move;
+            let rank = match color {
+                Color::White => 0,
+                Color::Black => 7,
+            };
+            let king = p.board[rank][4].ok_or(ParseMoveError::IllegalMoveError)?;
+            if king != GamePiece::new(Piece::King, color) {
+                return Err(ParseMoveError::IllegalMoveError);
+            }
+            return Ok(ChessMove::new(king, 4, rank, king_file, rank, None, None));
+        }
+
         let move_ex = Regex::new(ALGEBRAIC_REGEX).unwrap();
         let caps = move_ex
             .captures(sanitized)
@@ -57,16 +76,29 @@ impl ChessMove {
             .map(|pm| pm.as_str())
             .and_then(|p| Piece::from_str(p).ok());
         let moved_piece = p.board[rank1][file1].ok_or(ParseMoveError::IllegalMoveError)?;
-        let captured_piece = p.board[rank2][file2];
-        return Ok(ChessMove::new(
+        let mut captured_piece = p.board[rank2][file2];
+        // An en passant capture takes the pawn beside the moving pawn.
+        if moved_piece.piece == Piece::Pawn
+            && file1 != file2
+            && p.en_passant == Some(Square::new(file2, rank2))
+        {
+            captured_piece = p.board[rank1][file2];
+        }
+        Ok(ChessMove::new(
             moved_piece,
             file1,
-            rank1 as usize,
+            rank1,
             file2,
-            rank2 as usize,
+            rank2,
             promo_option,
             captured_piece,
-        ));
+        ))
+    }
+
+    // Returns whether the move is a king castling, which is the only way a king moves two files.
+    pub fn is_castle(&self) -> bool {
+        self.moved_piece.piece == Piece::King
+            && (self.o_file as isize - self.n_file as isize).abs() == 2
     }
 
     fn num_to_file(i: usize) -> &'static str {
@@ -97,14 +129,14 @@ impl PartialEq for ChessMove {
             _ => false,
         };
 
-        return equal && promo_equal;
+        equal && promo_equal
     }
 }
 
 impl Display for ChessMove {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let promo_str = match self.promo_piece {
-            Some(p) => p.to_string(),
+            Some(p) => p.to_string().to_lowercase(),
             None => "".to_owned(),
         };
         write!(
@@ -119,11 +151,23 @@ impl Display for ChessMove {
     }
 }
 
+#[derive(Debug)]
 pub enum ParseMoveError {
     PieceParseError,
     FileParseError,
     RankParseError,
     IllegalMoveError,
+    // More than one legal move matches the notation given.
+    Ambiguous,
+}
+
+// Returns the file the king lands on for O-O or O-O-O. Zeros are accepted in place of O's.
+fn parse_castle(castle: &str) -> Option<usize> {
+    match castle.trim_end_matches(&['+', '#'][..]) {
+        "o-o" | "0-0" => Some(6),
+        "o-o-o" | "0-0-0" => Some(2),
+        _ => None,
+    }
 }
 
 fn parse_file(file: &str) -> Result<usize, ParseMoveError> {
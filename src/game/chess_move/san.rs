@@ -0,0 +1,136 @@
+use super::{parse_castle, parse_file, parse_rank, ChessMove, ParseMoveError};
+use crate::game::game_piece::Piece;
+use crate::game::position::Position;
+use regex::Regex;
+use std::str::FromStr;
+
+const SAN_REGEX: &str = "^(?P<piece>[NBRQK])?(?P<file1>[a-h])?(?P<rank1>[1-8])?(?P<capture>x)?(?P<file2>[a-h])(?P<rank2>[1-8])(?:=?(?P<promotionPiece>[NBRQnbrq]))?$";
+
+impl ChessMove {
+    // Parses a move in Standard Algebraic Notation, e.g. Nf3, exd5, O-O or e8=Q+. The move is
+    // matched against the legal moves in the position, so it must be legal and unambiguous.
+    pub fn from_san(san: &str, p: &Position) -> Result<ChessMove, ParseMoveError> {
+        // Check, mate and annotation suffixes carry no information needed to find the move.
+        let sanitized = san.trim().trim_end_matches(&['+', '#', '!', '?'][..]);
+        let mut candidates = p.clone().get_moves();
+
+        if let Some(king_file) = parse_castle(&sanitized.to_lowercase()) {
+            candidates.retain(|m| m.is_castle() && m.n_file == king_file);
+        } else {
+            let move_ex = Regex::new(SAN_REGEX).unwrap();
+            let caps = move_ex
+                .captures(sanitized)
+                .ok_or(ParseMoveError::PieceParseError)?;
+            let piece = match caps.name("piece") {
+                Some(pm) => {
+                    Piece::from_str(pm.as_str()).map_err(|_| ParseMoveError::PieceParseError)?
+                }
+                None => Piece::Pawn,
+            };
+            let file1 = caps
+                .name("file1")
+                .map(|m| parse_file(m.as_str()))
+                .transpose()?;
+            let rank1 = caps
+                .name("rank1")
+                .map(|m| parse_rank(m.as_str()))
+                .transpose()?;
+            let file2 = parse_file(
+                caps.name("file2")
+                    .ok_or(ParseMoveError::FileParseError)?
+                    .as_str(),
+            )?;
+            let rank2 = parse_rank(
+                caps.name("rank2")
+                    .ok_or(ParseMoveError::RankParseError)?
+                    .as_str(),
+            )?;
+            let promo = caps
+                .name("promotionPiece")
+                .map(|pm| Piece::from_str(pm.as_str()).map_err(|_| ParseMoveError::PieceParseError))
+                .transpose()?;
+            let capture = caps.name("capture").is_some();
+
+            candidates.retain(|m| {
+                m.moved_piece.piece == piece
+                    && !m.is_castle()
+                    && m.n_file == file2
+                    && m.n_rank == rank2
+                    && file1.is_none_or(|f| f == m.o_file)
+                    && rank1.is_none_or(|r| r == m.o_rank)
+                    && m.promo_piece == promo
+                    && m.captured_piece.is_some() == capture
+            });
+        }
+
+        match candidates.len() {
+            0 => Err(ParseMoveError::IllegalMoveError),
+            1 => Ok(candidates[0]),
+            _ => Err(ParseMoveError::Ambiguous),
+        }
+    }
+
+    // Returns the move in Standard Algebraic Notation. The move must be legal in the position.
+    pub fn to_san(self, p: &Position) -> String {
+        let mut san = String::new();
+        let mut after = p.clone();
+
+        if self.is_castle() {
+            san += if self.n_file == 6 { "O-O" } else { "O-O-O" };
+        } else {
+            let capture = self.captured_piece.is_some();
+            if self.moved_piece.piece == Piece::Pawn {
+                if capture {
+                    san += Self::num_to_file(self.o_file);
+                }
+            } else {
+                san += &self.moved_piece.piece.to_string();
+                san += &self.disambiguation(&mut after);
+            }
+            if capture {
+                san.push('x');
+            }
+            san += Self::num_to_file(self.n_file);
+            san += &(self.n_rank + 1).to_string();
+            if let Some(promo) = self.promo_piece {
+                san += &format!("={}", promo);
+            }
+        }
+
+        if after.make_move(&self).is_ok() && after.in_check(after.side_to_move) {
+            san.push(if after.get_moves().is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+        san
+    }
+
+    // Returns the origin file, rank or square needed to tell the move apart from moves of the same
+    // kind of piece to the same square.
+    fn disambiguation(&self, p: &mut Position) -> String {
+        let rivals: Vec<ChessMove> = p
+            .get_moves()
+            .into_iter()
+            .filter(|m| {
+                m.moved_piece == self.moved_piece
+                    && m.n_file == self.n_file
+                    && m.n_rank == self.n_rank
+                    && (m.o_file != self.o_file || m.o_rank != self.o_rank)
+            })
+            .collect();
+
+        let file = Self::num_to_file(self.o_file);
+        let rank = (self.o_rank + 1).to_string();
+        if rivals.is_empty() {
+            String::new()
+        } else if rivals.iter().all(|m| m.o_file != self.o_file) {
+            file.to_owned()
+        } else if rivals.iter().all(|m| m.o_rank != self.o_rank) {
+            rank
+        } else {
+            format!("{}{}", file, rank)
+        }
+    }
+}
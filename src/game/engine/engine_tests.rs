@@ -0,0 +1,450 @@
+use super::*;
+use crate::game::engine::options::SearchOptions;
+use crate::game::engine::ordering::MoveOrdering;
+use crate::game::engine::transposition::Entry;
+use std::sync::atomic::AtomicBool;
+
+fn to_depth(depth: u32) -> SearchLimits {
+    SearchLimits {
+        depth: Some(depth),
+        ..SearchLimits::default()
+    }
+}
+
+// Searches the game within the limits, returning the best move and what was reported at each
+// depth.
+fn run_search(
+    g: &mut GameContext,
+    tt: &TranspositionTable,
+    limits: &SearchLimits,
+    options: &SearchOptions,
+) -> (Option<ChessMove>, Vec<SearchInfo>) {
+    let stop = AtomicBool::new(false);
+    let mut infos = vec![];
+    let best = search(g, tt, limits, options, &stop, |info| {
+        infos.push(info.clone())
+    });
+    (best, infos)
+}
+
+fn search_to_depth(
+    fen: &str,
+    depth: u32,
+    options: &SearchOptions,
+) -> (Option<ChessMove>, Vec<SearchInfo>) {
+    let mut g = GameContext::from_fen(fen).unwrap();
+    run_search(
+        &mut g,
+        &TranspositionTable::new(1),
+        &to_depth(depth),
+        options,
+    )
+}
+
+fn best_move(fen: &str, depth: u32) -> Option<ChessMove> {
+    search_to_depth(fen, depth, &SearchOptions::default()).0
+}
+
+// The score reported for the deepest search.
+fn final_score(infos: &[SearchInfo]) -> Score {
+    infos.last().unwrap().score
+}
+
+const ITALIAN: &str = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+
+#[test]
+fn finds_mate_in_one() {
+    let m = best_move("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3).unwrap();
+    assert_eq!(m.to_string(), "a1a8");
+}
+
+#[test]
+fn prefers_mate_over_material() {
+    // Taking the queen wins material, but Qb7 mates.
+    let m = best_move("k7/p7/2P5/8/3q4/8/1Q6/7K w - - 0 1", 2).unwrap();
+    assert_eq!(m.to_string(), "b2b7");
+}
+
+#[test]
+fn mated_and_stalemated_positions_have_no_move() {
+    assert!(best_move("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", 3).is_none());
+    assert!(best_move("7k/5Q2/8/8/8/8/8/K7 b - - 0 1", 3).is_none());
+}
+
+#[test]
+fn scores_mate_beyond_material() {
+    let (_, infos) = search_to_depth(
+        "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+        2,
+        &SearchOptions::default(),
+    );
+    assert_eq!(final_score(&infos).mate_in_moves(), Some(1));
+}
+
+#[test]
+fn repetition_is_a_draw() {
+    let mut g = GameContext::from_fen("7k/8/8/8/8/8/8/2Q4K b - - 0 1").unwrap();
+    for m in &["Kg8", "Qc2", "Kh8", "Qc1"] {
+        let chess_move = ChessMove::from_san(m, &g.position).unwrap();
+        g.make_move(chess_move).unwrap();
+    }
+
+    // A queen down, Black would rather repeat the position with Kg8.
+    let (m, infos) = run_search(
+        &mut g,
+        &TranspositionTable::new(1),
+        &to_depth(2),
+        &SearchOptions::default(),
+    );
+    assert_eq!(m.unwrap().to_string(), "h8g8");
+    assert_eq!(final_score(&infos), Score::DRAW);
+}
+
+#[test]
+fn insufficient_material_is_a_draw() {
+    // Capturing the rook leaves a knight against a lone king.
+    let (m, infos) = search_to_depth(
+        "4k3/8/8/8/8/8/3r4/4KN2 w - - 0 1",
+        1,
+        &SearchOptions::default(),
+    );
+    assert_eq!(m.unwrap().to_string(), "e1d2");
+    assert_eq!(final_score(&infos), Score::DRAW);
+}
+
+fn entry(tt: &TranspositionTable, key: u64) -> Option<(u32, Bound, Score)> {
+    tt.probe(key).map(
+        |Entry {
+             depth,
+             bound,
+             score,
+             ..
+         }| (depth, bound, score),
+    )
+}
+
+#[test]
+fn transposition_table_stores_and_replaces() {
+    let tt = TranspositionTable::new(1);
+    assert!(tt.size_in_bytes() <= 1024 * 1024);
+    assert!(TranspositionTable::new(2).size_in_bytes() > 1024 * 1024);
+    assert_eq!(entry(&tt, 42), None);
+
+    tt.store(42, 3, Bound::Exact, Score::cp(150), None);
+    assert_eq!(entry(&tt, 42), Some((3, Bound::Exact, Score::cp(150))));
+
+    // A shallower result for the same position doesn't replace a deeper one.
+    tt.store(42, 2, Bound::Lower, Score::cp(50), None);
+    assert_eq!(entry(&tt, 42), Some((3, Bound::Exact, Score::cp(150))));
+    tt.store(42, 4, Bound::Upper, Score::cp(-100), None);
+    assert_eq!(entry(&tt, 42), Some((4, Bound::Upper, Score::cp(-100))));
+
+    tt.clear();
+    assert_eq!(entry(&tt, 42), None);
+
+    // A table too small for even a megabyte has one slot, which every position shares. Another
+    // position replaces the one there, and they are told apart by their keys.
+    let tt = TranspositionTable::new(0);
+    tt.store(42, 3, Bound::Exact, Score::cp(150), None);
+    tt.store(43, 1, Bound::Exact, Score::DRAW, None);
+    assert_eq!(entry(&tt, 42), None);
+    assert_eq!(entry(&tt, 43), Some((1, Bound::Exact, Score::DRAW)));
+}
+
+#[test]
+fn transposition_table_keeps_moves_and_mates() {
+    let p = Position::from_fen("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let promotion = ChessMove::from_algebraic("a7b8q", &p).unwrap();
+    let tt = TranspositionTable::new(1);
+    tt.store(7, 5, Bound::Lower, Score::mated_in(3), Some(promotion));
+    let stored = tt.probe(7).unwrap();
+    assert_eq!(stored.score, Score::mated_in(3));
+    let m = stored.best_move.unwrap();
+    assert_eq!(m, promotion);
+    assert_eq!(m.moved_piece, promotion.moved_piece);
+    assert_eq!(m.captured_piece, promotion.captured_piece);
+}
+
+#[test]
+fn transposition_table_saves_work() {
+    let g = GameContext::from_fen(ITALIAN).unwrap();
+    let tt = TranspositionTable::new(1);
+    let nodes = || {
+        let (_, infos) = run_search(&mut g.clone(), &tt, &to_depth(3), &SearchOptions::default());
+        infos.last().unwrap().nodes
+    };
+    // The search is repeated with the results of the first in the table.
+    let first = nodes();
+    assert!(nodes() < first);
+    assert_eq!(tt.probe(g.position.hash).map(|e| e.depth), Some(3));
+}
+
+#[test]
+fn principal_variation_is_a_full_line_of_legal_moves() {
+    let g = GameContext::from_fen(ITALIAN).unwrap();
+    let (best, infos) = search_to_depth(ITALIAN, 4, &SearchOptions::default());
+    assert_eq!(infos.len(), 4);
+    assert_eq!(infos[3].pv.first().copied(), best);
+    for (depth, pv) in infos.into_iter().map(|info| info.pv).enumerate() {
+        // Each line is as long as the search was deep, as nothing ends it sooner.
+        assert_eq!(pv.len(), depth + 1);
+        let mut line = g.clone();
+        for chess_move in pv {
+            assert_eq!(line.make_move(chess_move), Ok(()));
+        }
+    }
+}
+
+#[test]
+fn hashfull_measures_the_table() {
+    let tt = TranspositionTable::new(1);
+    assert_eq!(tt.hashfull(), 0);
+    let (_, infos) = run_search(
+        &mut GameContext::new(),
+        &tt,
+        &to_depth(4),
+        &SearchOptions::default(),
+    );
+    let hashfull: Vec<usize> = infos.iter().map(|info| info.hashfull).collect();
+    assert!(hashfull.windows(2).all(|w| w[0] <= w[1]));
+    assert!(hashfull[3] > 0);
+    tt.clear();
+    assert_eq!(tt.hashfull(), 0);
+}
+
+#[test]
+fn node_limit_ends_the_search() {
+    let limits = SearchLimits {
+        nodes: Some(5000),
+        ..SearchLimits::default()
+    };
+    let (best, infos) = run_search(
+        &mut GameContext::new(),
+        &TranspositionTable::new(1),
+        &limits,
+        &SearchOptions::default(),
+    );
+    assert!(best.is_some());
+    assert!(infos.iter().all(|info| info.nodes <= 5000));
+}
+
+#[test]
+fn time_limit_aborts_mid_depth() {
+    let limits = SearchLimits {
+        movetime: Some(200),
+        ..SearchLimits::default()
+    };
+    let start = std::time::Instant::now();
+    let (best, infos) = run_search(
+        &mut GameContext::new(),
+        &TranspositionTable::new(1),
+        &limits,
+        &SearchOptions::default(),
+    );
+    assert!(start.elapsed() < Duration::from_millis(1000));
+    // The move played is the best of the last depth to finish.
+    let last = infos.last().unwrap();
+    assert!(last.depth < limits::MAX_DEPTH);
+    assert_eq!(best, last.pv.first().copied());
+}
+
+// Positions where taking or leaving material looks good at the last ply, but loses it to a
+// recapture beyond it, with the move the engine used to play.
+const HORIZON_POSITIONS: [(&str, &str); 4] = [
+    // The d5 pawn is defended, so taking it loses the queen.
+    ("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1", "d2d5"),
+    // The knight is defended by a pawn, so taking it loses the exchange.
+    ("4k3/8/4p3/3n4/8/8/8/3RK3 w - - 0 1", "d1d5"),
+    // The queen is attacked, and the pawn attacking it is defended.
+    ("4k3/8/1p6/2p5/3Q4/8/8/4K3 w - - 0 1", "d4c5"),
+    // The e5 pawn is defended, so taking it loses the bishop.
+    ("4k3/8/5p2/4p3/8/8/1B6/4K3 w - - 0 1", "b2e5"),
+];
+
+#[test]
+fn does_not_hang_pieces_at_the_horizon() {
+    for (fen, losing) in HORIZON_POSITIONS.iter() {
+        let g = GameContext::from_fen(fen).unwrap();
+        let losing = ChessMove::from_algebraic(losing, &g.position).unwrap();
+        for depth in 1..=2 {
+            assert_ne!(
+                best_move(fen, depth),
+                Some(losing),
+                "{} at depth {}",
+                fen,
+                depth
+            );
+        }
+    }
+}
+
+#[test]
+fn quiescence_sees_the_recapture() {
+    let (_, infos) = search_to_depth(
+        "4k3/8/1p6/2p5/3Q4/8/8/4K3 w - - 0 1",
+        1,
+        &SearchOptions::default(),
+    );
+    // The queen escapes, keeping White's material.
+    assert_eq!(infos.len(), 1);
+    assert_eq!(final_score(&infos), Score::cp(700));
+}
+
+fn moves(g: &GameContext, moves: &[&str]) -> Vec<ChessMove> {
+    moves
+        .iter()
+        .map(|m| ChessMove::from_algebraic(m, &g.position).unwrap())
+        .collect()
+}
+
+#[test]
+fn moves_are_ordered_by_promise() {
+    let g = GameContext::from_fen("4k3/1P6/8/3q1r2/4P3/2N5/8/4K3 w - - 0 1").unwrap();
+    let mut ordering = MoveOrdering::new(4);
+    let killer = moves(&g, &["e1d2"])[0];
+    ordering.record_cutoff(killer, 1, 3, None);
+    let mut to_sort = moves(
+        &g,
+        &["e1f2", "e1d2", "e4f5", "c3d5", "e4d5", "b7b8q", "c3b5"],
+    );
+    let hash_move = Some(to_sort[6]);
+    ordering.sort(&mut to_sort, 1, hash_move, None);
+    assert_eq!(
+        to_sort,
+        moves(
+            &g,
+            &["c3b5", "e4d5", "b7b8q", "c3d5", "e4f5", "e1d2", "e1f2"]
+        )
+    );
+
+    // Killers belong to the ply they were found at, but the history of a move counts anywhere.
+    ordering.sort(&mut to_sort, 2, None, None);
+    assert_eq!(to_sort[4..], moves(&g, &["e1d2", "c3b5", "e1f2"])[..]);
+}
+
+#[test]
+fn first_moves_usually_cause_the_cutoff() {
+    let (_, infos) = search_to_depth(ITALIAN, 4, &SearchOptions::none());
+    let (first, all) = (infos[3].first_move_cutoffs, infos[3].cutoffs);
+    assert!(first * 10 >= all * 8, "{} of {}", first, all);
+}
+
+#[test]
+fn aspiration_window_widens_for_a_mate() {
+    let (_, infos) = search_to_depth("6k1/8/8/6K1/8/8/8/R7 w - - 0 1", 5, &SearchOptions::none());
+    // The mate is found beyond the depth the window is first used at, far outside it.
+    assert_eq!(infos[3].score, Score::cp(500));
+    assert!(infos[4].score.is_mate());
+}
+
+#[test]
+fn scores_display_in_pawns_or_moves_to_mate() {
+    assert_eq!(Score::cp(125).to_string(), "+1.25");
+    assert_eq!(Score::cp(-50).to_string(), "-0.50");
+    assert_eq!(Score::DRAW.to_string(), "+0.00");
+    // Mated at ply 9 from the root is mate in 5 for the side to move; at ply 6, mated in 3.
+    assert_eq!((-Score::mated_in(9)).to_string(), "#5");
+    assert_eq!(Score::mated_in(6).to_string(), "#-3");
+    assert_eq!(Score::mated_in(0).mate_in_moves(), Some(0));
+    assert_eq!(Score::cp(900).mate_in_moves(), None);
+}
+
+#[test]
+fn mate_scores_count_from_the_stored_position() {
+    // A mate 5 plies from the root, found in a position 2 plies from the root, is 3 plies from it.
+    let mate = -Score::mated_in(5);
+    assert_eq!(mate.to_tt(2), -Score::mated_in(3));
+    // Reached again at 4 plies from the root, the same mate is 7 plies away.
+    assert_eq!(mate.to_tt(2).from_tt(4), -Score::mated_in(7));
+    assert_eq!(Score::mated_in(5).to_tt(2), Score::mated_in(3));
+    assert_eq!(Score::cp(300).to_tt(2), Score::cp(300));
+}
+
+#[test]
+fn mate_distance_holds_as_the_search_deepens() {
+    let (_, infos) = search_to_depth("7k/8/8/5K2/8/8/8/R7 w - - 0 1", 5, &SearchOptions::none());
+    let scores: Vec<String> = infos.iter().map(|info| info.score.to_string()).collect();
+    assert_eq!(scores[2..], ["#2", "#2", "#2"]);
+}
+
+#[test]
+fn each_pruning_technique_searches_fewer_nodes() {
+    let nodes = |options: &SearchOptions| {
+        let (_, infos) = search_to_depth(ITALIAN, 4, options);
+        infos.last().unwrap().nodes
+    };
+    let none = SearchOptions::none();
+    let plain = nodes(&none);
+    let techniques = [
+        SearchOptions {
+            null_move: true,
+            ..none
+        },
+        SearchOptions {
+            late_move_reductions: true,
+            ..none
+        },
+        SearchOptions {
+            reverse_futility: true,
+            ..none
+        },
+        SearchOptions {
+            futility: true,
+            ..none
+        },
+    ];
+    for options in techniques.iter() {
+        assert!(nodes(options) < plain, "{:?}", options);
+    }
+}
+
+#[test]
+fn check_extensions_find_mates_sooner() {
+    let score = |options: &SearchOptions| {
+        let (_, infos) = search_to_depth("8/4k3/R7/8/8/8/8/1R5K w - - 0 1", 2, options);
+        final_score(&infos).to_string()
+    };
+    let none = SearchOptions::none();
+    assert_eq!(score(&none), "+10.00");
+    let extended = SearchOptions {
+        check_extensions: true,
+        ..none
+    };
+    // The mating check is searched beyond the nominal depth.
+    assert_eq!(score(&extended), "#2");
+}
+
+#[test]
+fn null_move_is_not_tried_where_zugzwang_is_likely() {
+    let pawns = Position::from_fen("4k3/4p3/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+    assert!(!null_move_safe(&pawns, &[]));
+    let knight = Position::from_fen("4k3/4p3/8/8/8/8/4P3/4KN2 w - - 0 1").unwrap();
+    assert!(null_move_safe(&knight, &[]));
+    // Nor twice in a row.
+    assert!(!null_move_safe(&knight, &[None]));
+}
+
+#[test]
+fn helper_threads_share_the_search() {
+    let options = SearchOptions {
+        threads: 4,
+        ..SearchOptions::default()
+    };
+    let (m, infos) = search_to_depth("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 4, &options);
+    assert_eq!(m.unwrap().to_string(), "a1a8");
+    // Only the main thread reports, once for each depth.
+    let depths: Vec<u32> = infos.iter().map(|info| info.depth).collect();
+    assert_eq!(depths, vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn one_thread_searches_deterministically() {
+    let run = || {
+        let (_, infos) = search_to_depth(ITALIAN, 5, &SearchOptions::default());
+        infos
+            .into_iter()
+            .map(|info| (info.nodes, info.score, info.pv))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(run(), run());
+}
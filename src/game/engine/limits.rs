@@ -0,0 +1,107 @@
+use super::THINK_DEPTH;
+use crate::game::color::Color;
+use std::time::{Duration, Instant};
+
+// The deepest the engine will look when only the clock or a node count limits the search.
+pub const MAX_DEPTH: u32 = 64;
+// Games are assumed to last this many more moves when the number isn't known.
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+// Kept in reserve so the engine doesn't lose on time to communication delays.
+const MOVE_OVERHEAD_MS: u64 = 50;
+// How many nodes are searched between looks at the clock.
+const CLOCK_CHECK_INTERVAL: u64 = 1024;
+
+// What may bring a search to an end, besides being told to stop. Times are in milliseconds.
+#[derive(Debug, Default, PartialEq)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<u64>,
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: Option<u64>,
+    pub binc: Option<u64>,
+    pub movestogo: Option<u64>,
+    pub infinite: bool,
+}
+
+impl SearchLimits {
+    // Decides how long to think for, or None if the search isn't limited by time.
+    pub fn think_time(&self, color: Color) -> Option<Duration> {
+        if self.infinite {
+            return None;
+        }
+        if let Some(movetime) = self.movetime {
+            return Some(Duration::from_millis(movetime));
+        }
+        let (time, inc) = match color {
+            Color::White => (self.wtime?, self.winc.unwrap_or(0)),
+            Color::Black => (self.btime?, self.binc.unwrap_or(0)),
+        };
+        let moves_to_go = self.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+        let budget = time / moves_to_go + inc;
+        let available = time.saturating_sub(MOVE_OVERHEAD_MS);
+        Some(Duration::from_millis(budget.min(available)))
+    }
+
+    pub fn max_depth(&self) -> u32 {
+        match self.depth {
+            Some(depth) => depth.max(1),
+            None if self.infinite
+                || self.nodes.is_some()
+                || self.movetime.is_some()
+                || self.wtime.is_some()
+                || self.btime.is_some() =>
+            {
+                MAX_DEPTH
+            }
+            None => THINK_DEPTH,
+        }
+    }
+}
+
+// Keeps a search within the time given to its move.
+pub struct TimeManager {
+    start: Instant,
+    // The search is abandoned once this much time has passed.
+    think_time: Option<Duration>,
+    // A depth isn't started after this much time, as it would rarely finish in what's left.
+    soft_limit: Option<Duration>,
+    nodes: Option<u64>,
+}
+
+impl TimeManager {
+    pub fn new(limits: &SearchLimits, color: Color) -> TimeManager {
+        let think_time = limits.think_time(color);
+        // A fixed time per move may as well be spent in full.
+        let soft_limit = match limits.movetime {
+            Some(_) => think_time,
+            None => think_time.map(|t| t / 2),
+        };
+        TimeManager {
+            start: Instant::now(),
+            think_time,
+            soft_limit,
+            nodes: limits.nodes,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    // Whether the search must stop now, having searched the given number of nodes. The clock is
+    // only read every so many nodes, as reading it is slow next to searching a node.
+    pub fn out_of_time(&self, nodes: u64) -> bool {
+        if self.nodes.is_some_and(|limit| nodes >= limit) {
+            return true;
+        }
+        nodes.is_multiple_of(CLOCK_CHECK_INTERVAL)
+            && self.think_time.is_some_and(|t| self.elapsed() >= t)
+    }
+
+    // Whether there is time left to search another depth.
+    pub fn can_deepen(&self) -> bool {
+        self.soft_limit.is_none_or(|t| self.elapsed() < t)
+    }
+}
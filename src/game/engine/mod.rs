@@ -0,0 +1,575 @@
+#[cfg(test)]
+mod engine_tests;
+pub mod limits;
+pub mod options;
+mod ordering;
+pub mod score;
+pub mod transposition;
+
+use super::chess_move::ChessMove;
+use super::game_context::GameContext;
+use super::game_status::count_repetitions;
+use super::position::Position;
+use limits::{SearchLimits, TimeManager};
+use options::SearchOptions;
+use ordering::MoveOrdering;
+use score::Score;
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::thread;
+use std::time::Duration;
+use transposition::{Bound, TranspositionTable};
+
+pub static THINK_DEPTH: u32 = 6;
+// The most moves deep a line may be searched.
+const MAX_PLY: usize = 128;
+// How far either side of the last depth's evaluation the next depth first searches, in
+// centipawns. Depths shallower than ASPIRATION_DEPTH vary too much to be worth it.
+const ASPIRATION_WINDOW: i32 = 50;
+const ASPIRATION_DEPTH: u32 = 4;
+// Null move searches are reduced by this much more than a move, and only tried this deep.
+const NULL_MOVE_REDUCTION: u32 = 2;
+const NULL_MOVE_DEPTH: u32 = 3;
+// Futility pruning and its reverse only apply this near the horizon, with margins per ply of
+// depth remaining, in centipawns.
+const FUTILITY_DEPTH: u32 = 3;
+const FUTILITY_MARGIN: i32 = 200;
+const REVERSE_FUTILITY_MARGIN: i32 = 120;
+// Late move reductions only apply this deep, to moves at least this far down the ordering.
+const LMR_DEPTH: u32 = 3;
+const LMR_MOVES: usize = 3;
+
+// SearchInfo describes a completed iteration of the search, for reporting to the user or a GUI.
+#[derive(Clone, Debug)]
+pub struct SearchInfo {
+    pub depth: u32,
+    // The evaluation from the perspective of the side to move.
+    pub score: Score,
+    pub nodes: u64,
+    // How many nodes were cut off, and how many of those by the first move searched. The closer
+    // the two, the better moves are ordered.
+    pub cutoffs: u64,
+    pub first_move_cutoffs: u64,
+    pub time: Duration,
+    pub pv: Vec<ChessMove>,
+    // How full the transposition table is, in thousandths.
+    pub hashfull: usize,
+}
+
+// Counts nodes and watches for a request to stop while calculate runs.
+struct Search<'a> {
+    stop: &'a AtomicBool,
+    time: TimeManager,
+    // Set once the search has used up its time or nodes.
+    timed_out: bool,
+    nodes: u64,
+    cutoffs: u64,
+    first_move_cutoffs: u64,
+    // The Zobrist keys of the game so far and of the line being searched.
+    history: Vec<u64>,
+    // The moves of the line being searched, with None for a null move.
+    line: Vec<Option<ChessMove>>,
+    options: SearchOptions,
+    ordering: MoveOrdering,
+    // How many moves into the line being searched the current position is.
+    ply: usize,
+    tt: &'a TranspositionTable,
+    // The best line found from each ply of the line being searched. The line at a ply is the best
+    // move there followed by the line at the next ply, so no memory is allocated as lines change.
+    pv: Vec<Vec<ChessMove>>,
+    // The moves at the root with their evaluations from the last completed depth, best first.
+    root_moves: Vec<(ChessMove, Score)>,
+}
+
+impl Search<'_> {
+    fn stopped(&self) -> bool {
+        self.timed_out || self.stop.load(AtomicOrdering::Relaxed)
+    }
+
+    // Counts a node on entering it, at which point no line has been found from it yet.
+    fn visit(&mut self) {
+        self.nodes += 1;
+        self.pv[self.ply].clear();
+        if self.time.out_of_time(self.nodes) {
+            self.timed_out = true;
+        }
+    }
+
+    // The move that led to the position being searched, unless it was a null move.
+    fn previous_move(&self) -> Option<ChessMove> {
+        self.line.last().copied().flatten()
+    }
+
+    // Records chess_move as the best at the current ply, followed by the best line after it.
+    fn update_pv(&mut self, chess_move: ChessMove) {
+        let (line, rest) = self.pv.split_at_mut(self.ply + 1);
+        let line = &mut line[self.ply];
+        line.clear();
+        line.push(chess_move);
+        line.extend_from_slice(&rest[0]);
+    }
+}
+
+pub fn think(g: &mut GameContext, tt: &TranspositionTable) -> ChessMove {
+    let stop = AtomicBool::new(false);
+    let (limits, options) = (SearchLimits::default(), SearchOptions::default());
+    search(g, tt, &limits, &options, &stop, |info| {
+        let pv = info
+            .pv
+            .iter()
+            .map(|m| m.to_string())
+            .collect::<Vec<String>>();
+        println!(
+            "depth {} eval {} nodes {} cutoffs {}/{} pv {}",
+            info.depth,
+            info.score,
+            info.nodes,
+            info.first_move_cutoffs,
+            info.cutoffs,
+            pv.join(" ")
+        );
+    })
+    .expect("Think failed!")
+}
+
+// Searches one depth deeper at a time until the limits are reached or stop is set, reporting each
+// completed depth. Returns the best move of the last completed depth, or None if there are no
+// legal moves.
+// With more than one thread, helper threads search the same position alongside, sharing what they
+// find through the transposition table so that the main thread finds it there rather than
+// searching it itself. Only the main thread's results are reported and played. With one thread,
+// nothing is shared and the search is deterministic.
+pub fn search<F: FnMut(&SearchInfo)>(
+    g: &mut GameContext,
+    tt: &TranspositionTable,
+    limits: &SearchLimits,
+    options: &SearchOptions,
+    stop: &AtomicBool,
+    report: F,
+) -> Option<ChessMove> {
+    if options.threads <= 1 {
+        return iterate(g, tt, limits, options, stop, 1, report);
+    }
+    // The helpers search until the main thread is done.
+    let helpers_stop = AtomicBool::new(false);
+    let helper_limits = SearchLimits {
+        infinite: true,
+        ..SearchLimits::default()
+    };
+    thread::scope(|scope| {
+        for helper in 1..options.threads {
+            let mut g = g.clone();
+            let (limits, stop) = (&helper_limits, &helpers_stop);
+            // Starting at different depths keeps the helpers from all searching the same
+            // positions at the same time.
+            let first_depth = 1 + helper as u32 % 2;
+            scope.spawn(move || iterate(&mut g, tt, limits, options, stop, first_depth, |_| {}));
+        }
+        let best_move = iterate(g, tt, limits, options, stop, 1, report);
+        helpers_stop.store(true, AtomicOrdering::Relaxed);
+        best_move
+    })
+}
+
+// Searches one depth deeper at a time from first_depth, as search does on a single thread.
+fn iterate<F: FnMut(&SearchInfo)>(
+    g: &mut GameContext,
+    tt: &TranspositionTable,
+    limits: &SearchLimits,
+    options: &SearchOptions,
+    stop: &AtomicBool,
+    first_depth: u32,
+    mut report: F,
+) -> Option<ChessMove> {
+    let p = &mut g.position;
+    let mut s = Search {
+        stop,
+        time: TimeManager::new(limits, p.side_to_move),
+        timed_out: false,
+        nodes: 0,
+        cutoffs: 0,
+        first_move_cutoffs: 0,
+        history: g.position_keys.clone(),
+        line: Vec::with_capacity(MAX_PLY),
+        options: *options,
+        ordering: MoveOrdering::new(MAX_PLY),
+        ply: 0,
+        tt,
+        pv: (0..=MAX_PLY).map(|_| Vec::with_capacity(MAX_PLY)).collect(),
+        root_moves: p
+            .get_moves()
+            .into_iter()
+            .map(|m| (m, -Score::INFINITY))
+            .collect(),
+    };
+    // Should the search be stopped before the first depth completes, any legal move will do.
+    let mut best_move = s.root_moves.first().map(|&(m, _)| m);
+    let mut last_eval = Score::DRAW;
+
+    for depth in first_depth..=limits.max_depth().min(MAX_PLY as u32) {
+        let (eval, chess_move) = aspirate(p, depth, last_eval, &mut s);
+        if s.stopped() || chess_move.is_none() {
+            break;
+        }
+        last_eval = eval;
+        best_move = chess_move;
+        // The next depth searches the moves that did best at this one first.
+        s.root_moves.sort_by_key(|&(_, eval)| Reverse(eval));
+        report(&SearchInfo {
+            depth,
+            score: eval,
+            nodes: s.nodes,
+            cutoffs: s.cutoffs,
+            first_move_cutoffs: s.first_move_cutoffs,
+            time: s.time.elapsed(),
+            pv: s.pv[0].clone(),
+            hashfull: s.tt.hashfull(),
+        });
+        if !s.time.can_deepen() {
+            break;
+        }
+    }
+    best_move
+}
+
+// Searches the root to depth in a narrow window around the evaluation of the last depth, which
+// prunes more than a full window. Should the evaluation fall outside it, the window is widened
+// and the search repeated.
+fn aspirate(
+    p: &mut Position,
+    depth: u32,
+    last_eval: Score,
+    s: &mut Search,
+) -> (Score, Option<ChessMove>) {
+    if depth < ASPIRATION_DEPTH {
+        return calculate(p, depth, -Score::INFINITY, Score::INFINITY, s);
+    }
+    let mut delta = ASPIRATION_WINDOW;
+    let (mut alpha, mut beta) = (last_eval - delta, last_eval + delta);
+    loop {
+        let (eval, chess_move) = calculate(p, depth, alpha, beta, s);
+        if s.stopped() {
+            return (eval, chess_move);
+        }
+        delta *= 2;
+        if eval <= alpha {
+            alpha = Score::max(eval - delta, -Score::INFINITY);
+        } else if eval >= beta {
+            beta = Score::min(eval + delta, Score::INFINITY);
+        } else {
+            return (eval, chess_move);
+        }
+    }
+}
+
+// Calculate is an implementation of negaMax, searching all moves after the first with a null
+// window to prove them no better (principal variation search, a form of negaScout).
+/* Alpha is like a higher order bestSoFar variable. For the maximizer, it is the minimum score we are assured in other branches that we have calculated in parent nodes.
+Therefore, if the minimizer in the current branch assures a worse score for us with any of its replies, we can give up on the current branch altogether as the maximizer.
+This logic is somewhat muddied by the negamax take on minimax. Alpha typically tracks the maximizer's assured score and beta typically tracks the
+minimizer's assured score. In negaMax, we negate the minimizer's result in the call to Calculate() which allows us to share the calculate function
+between the two. In order for alpha and beta to work, their values must match with whether the minimizer or the maximizer is evaluating. Now, when
+we pass from the maximizer to the minimizer, we give the minimizer beta as its alpha and vice versa.
+A null window, where beta is barely above alpha, can't find a move's evaluation, only whether it beats alpha. That
+is much quicker to answer, so after the first move, which is likely best when moves are well ordered, the rest are
+searched with one. A move found to beat alpha is searched again with the full window to find how much it does by. */
+fn calculate(
+    p: &mut Position,
+    depth: u32,
+    mut alpha: Score,
+    beta: Score,
+    s: &mut Search,
+) -> (Score, Option<ChessMove>) {
+    // At final depth, settle the captures left hanging before evaluating.
+    if depth == 0 {
+        return (quiesce(p, alpha, beta, s), None);
+    }
+
+    s.visit();
+    if s.stopped() {
+        return (Score::DRAW, None);
+    }
+    if s.ply >= MAX_PLY {
+        return (evaluate(p), None);
+    }
+
+    // A search of this position at least as deep may already have settled it. Positions in the
+    // principal variation are always searched, so that it is complete.
+    let alpha_orig = alpha;
+    let pv_node = beta > alpha + 1;
+    let entry = s.tt.probe(p.hash);
+    if let Some(entry) = entry.filter(|entry| !pv_node && entry.depth >= depth) {
+        let score = entry.score.from_tt(s.ply);
+        let settled = match entry.bound {
+            Bound::Exact => true,
+            Bound::Lower => score >= beta,
+            Bound::Upper => score <= alpha,
+        };
+        if settled {
+            s.pv[s.ply].extend(entry.best_move);
+            return (score, entry.best_move);
+        }
+    }
+
+    // Outside the principal variation, a position whose evaluation is far from the window may
+    // be pruned before it is searched in full. In check, the evaluation means little.
+    let in_check = p.in_check(p.side_to_move);
+    let static_eval = if pv_node || in_check {
+        None
+    } else {
+        Some(evaluate(p))
+    };
+    if let Some(eval) = static_eval {
+        // So far above beta near the horizon that no reply is likely to bring it back.
+        if s.options.reverse_futility
+            && depth <= FUTILITY_DEPTH
+            && !beta.is_mate()
+            && eval - REVERSE_FUTILITY_MARGIN * depth as i32 >= beta
+        {
+            return (eval, None);
+        }
+        if s.options.null_move
+            && eval >= beta
+            && depth >= NULL_MOVE_DEPTH
+            && null_move_safe(p, &s.line)
+        {
+            let eval = null_move_search(p, depth, beta, s);
+            if s.stopped() {
+                return (Score::DRAW, None);
+            }
+            if eval >= beta {
+                return (eval, None);
+            }
+        }
+    }
+    // So far below alpha near the horizon that only winning material is likely to bring it back.
+    let futile = s.options.futility
+        && depth <= FUTILITY_DEPTH
+        && static_eval.is_some_and(|eval| eval + FUTILITY_MARGIN * depth as i32 <= alpha);
+
+    // The root moves are already ordered by the last depth. Elsewhere, the best move found for
+    // the position before is the most likely to be best again.
+    let moves = if s.ply == 0 {
+        s.root_moves.iter().map(|&(m, _)| m).collect()
+    } else {
+        let mut moves = p.get_moves();
+        let hash_move = entry.and_then(|entry| entry.best_move);
+        s.ordering
+            .sort(&mut moves, s.ply, hash_move, s.previous_move());
+        moves
+    };
+
+    // Without moves the game is over, drawn by stalemate or lost to checkmate.
+    if moves.is_empty() {
+        if in_check {
+            return (Score::mated_in(s.ply), None);
+        }
+        return (Score::DRAW, None);
+    }
+
+    // Calculate possible moves
+    let (mut best_eval, mut best_move) = (-Score::INFINITY, None);
+    for (i, chess_move) in moves.into_iter().enumerate() {
+        // Make the move.
+        let undo = p
+            .make_move(&chess_move)
+            .unwrap_or_else(|_| panic!("Failed to make move {}", chess_move));
+        let gives_check = p.in_check(p.side_to_move);
+        let quiet = ordering::is_quiet(&chess_move) && !gives_check;
+        if futile && i > 0 && quiet {
+            p.undo_move(&chess_move, undo)
+                .expect("Failed to roll back move.");
+            continue;
+        }
+        // A check is searched a move deeper, as the reply is forced and the line is sharp.
+        let new_depth = if s.options.check_extensions && gives_check {
+            depth
+        } else {
+            depth - 1
+        };
+        // Moves this late in the ordering rarely turn out best, so they are first searched less
+        // deeply, and only searched in full should they beat alpha.
+        let reduction = if s.options.late_move_reductions
+            && depth >= LMR_DEPTH
+            && i >= LMR_MOVES
+            && quiet
+            && !in_check
+        {
+            1 + u32::from(depth >= 2 * LMR_DEPTH && i >= 3 * LMR_MOVES)
+        } else {
+            0
+        };
+
+        s.history.push(p.hash);
+        s.line.push(Some(chess_move));
+        s.ply += 1;
+        let eval = if is_drawn(p, &s.history) {
+            s.pv[s.ply].clear();
+            Score::DRAW
+        } else if i == 0 {
+            -calculate(p, new_depth, -beta, -alpha, s).0
+        } else {
+            let reduced = new_depth.saturating_sub(reduction);
+            let mut eval = -calculate(p, reduced, -alpha - 1, -alpha, s).0;
+            if reduction > 0 && eval > alpha {
+                eval = -calculate(p, new_depth, -alpha - 1, -alpha, s).0;
+            }
+            if eval > alpha && eval < beta {
+                eval = -calculate(p, new_depth, -beta, -alpha, s).0;
+            }
+            eval
+        };
+        s.history.pop();
+        s.line.pop();
+        s.ply -= 1;
+
+        // Roll back move.
+        p.undo_move(&chess_move, undo)
+            .expect("Failed to roll back move.");
+
+        if s.stopped() {
+            // The result of an interrupted search can't be trusted.
+            return (best_eval, best_move);
+        }
+        if s.ply == 0 {
+            s.root_moves[i].1 = eval;
+        }
+        if eval > best_eval {
+            best_eval = eval;
+            best_move = Some(chess_move);
+            s.update_pv(chess_move);
+        }
+
+        alpha = Score::max(alpha, best_eval);
+        if alpha >= beta {
+            s.cutoffs += 1;
+            if i == 0 {
+                s.first_move_cutoffs += 1;
+            }
+            let previous = s.previous_move();
+            s.ordering.record_cutoff(chess_move, s.ply, depth, previous);
+            break;
+        }
+    }
+
+    let bound = if best_eval <= alpha_orig {
+        Bound::Upper
+    } else if best_eval >= beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    s.tt.store(p.hash, depth, bound, best_eval.to_tt(s.ply), best_move);
+
+    (best_eval, best_move)
+}
+
+// Whether passing the move can be expected to be no better than the best move. That fails in
+// zugzwang, where every move makes things worse, which is common with only pawns left. Two passes
+// in a row would search the same position again, shallower.
+fn null_move_safe(p: &Position, line: &[Option<ChessMove>]) -> bool {
+    line.last() != Some(&None) && p.has_non_pawn_material(p.side_to_move)
+}
+
+// Passes the move and searches the position shallower with a null window at beta. Should the
+// side to move still fail high without moving, a real move would very likely do so too.
+fn null_move_search(p: &mut Position, depth: u32, beta: Score, s: &mut Search) -> Score {
+    let reduction = NULL_MOVE_REDUCTION + depth / 6;
+    let undo = p.make_null_move();
+    s.history.push(p.hash);
+    s.line.push(None);
+    s.ply += 1;
+    let eval = -calculate(p, depth.saturating_sub(1 + reduction), -beta, -beta + 1, s).0;
+    s.history.pop();
+    s.line.pop();
+    s.ply -= 1;
+    p.undo_null_move(undo);
+    // A mate found after passing isn't proven, as passing isn't allowed.
+    if eval.is_mate() {
+        Score::min(eval, beta)
+    } else {
+        eval
+    }
+}
+
+// Searches captures and promotions until the position is quiet, so that the evaluation isn't of a
+// position where a piece is about to be lost. The side to move may instead stand pat on the
+// evaluation, as it need not capture. In check, every move is searched, as standing pat may be
+// impossible.
+fn quiesce(p: &mut Position, mut alpha: Score, beta: Score, s: &mut Search) -> Score {
+    s.visit();
+    if s.stopped() {
+        return Score::DRAW;
+    }
+    if s.ply >= MAX_PLY {
+        return evaluate(p);
+    }
+
+    let in_check = p.in_check(p.side_to_move);
+    let mut best_eval = -Score::INFINITY;
+    if !in_check {
+        best_eval = evaluate(p);
+        if best_eval >= beta {
+            return best_eval;
+        }
+        alpha = Score::max(alpha, best_eval);
+    }
+
+    let mut moves = p.get_moves();
+    if moves.is_empty() {
+        return if in_check {
+            Score::mated_in(s.ply)
+        } else {
+            Score::DRAW
+        };
+    }
+    if !in_check {
+        moves.retain(|m| m.captured_piece.is_some() || m.promo_piece.is_some());
+    }
+    ordering::sort_captures(&mut moves);
+
+    for chess_move in moves {
+        let undo = p
+            .make_move(&chess_move)
+            .unwrap_or_else(|_| panic!("Failed to make move {}", chess_move));
+        s.history.push(p.hash);
+        s.ply += 1;
+        let eval = if is_drawn(p, &s.history) {
+            Score::DRAW
+        } else {
+            -quiesce(p, -beta, -alpha, s)
+        };
+        s.history.pop();
+        s.ply -= 1;
+        p.undo_move(&chess_move, undo)
+            .expect("Failed to roll back move.");
+
+        if s.stopped() {
+            return best_eval;
+        }
+        best_eval = Score::max(best_eval, eval);
+        alpha = Score::max(alpha, best_eval);
+        if alpha >= beta {
+            break;
+        }
+    }
+    best_eval
+}
+
+// Whether the position reached is drawn by rule. A position is drawn as soon as it repeats, since
+// whatever came of it the first time can be played again.
+fn is_drawn(p: &mut Position, history: &[u64]) -> bool {
+    if count_repetitions(history, p.halfmove_clock) >= 2 || p.has_insufficient_material() {
+        return true;
+    }
+    // Checkmate on the move that completes the fifty moves still wins.
+    p.halfmove_clock >= 100 && !(p.in_check(p.side_to_move) && p.get_moves().is_empty())
+}
+
+// TODO
+// Evaluates the position from the perspective of the side to move.
+fn evaluate(p: &Position) -> Score {
+    let color = p.side_to_move;
+    // For now, let's play like a child. Maximize material.
+    Score::cp(p.sum_material(color) - p.sum_material(color.opp_color()))
+}
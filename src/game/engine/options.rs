@@ -0,0 +1,48 @@
+// The most threads a search may be given.
+pub const MAX_THREADS: usize = 64;
+
+// How the search is carried out. Besides the number of threads, these are which of the search's
+// selective techniques are used. Each prunes or extends the search where it is likely to make no
+// difference to the result, and can be turned off to measure what it's worth.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SearchOptions {
+    // Threads searching the position, sharing the transposition table. One is deterministic.
+    pub threads: usize,
+    // Skip a move, and prune the position if it still fails high in a shallower search.
+    pub null_move: bool,
+    // Search quiet moves late in the ordering less deeply unless they prove better than alpha.
+    pub late_move_reductions: bool,
+    // Prune positions near the horizon whose evaluation is far above beta.
+    pub reverse_futility: bool,
+    // Skip quiet moves near the horizon when the evaluation is far below alpha.
+    pub futility: bool,
+    // Search a move that gives check one move deeper.
+    pub check_extensions: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            threads: 1,
+            null_move: true,
+            late_move_reductions: true,
+            reverse_futility: true,
+            futility: true,
+            check_extensions: true,
+        }
+    }
+}
+
+impl SearchOptions {
+    // Plain alpha-beta on one thread, with none of the techniques.
+    pub fn none() -> SearchOptions {
+        SearchOptions {
+            threads: 1,
+            null_move: false,
+            late_move_reductions: false,
+            reverse_futility: false,
+            futility: false,
+            check_extensions: false,
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::game::chess_move::ChessMove;
+use crate::game::color::Color;
+use crate::game::game_piece::{GamePiece, Piece};
+use std::cmp::Reverse;
+
+// Moves are searched in order of these scores, highest first. Quiet moves score by their history,
+// which is kept below KILLER_SCORE.
+const HASH_MOVE_SCORE: i32 = 3_000_000;
+const CAPTURE_SCORE: i32 = 2_000_000;
+const KILLER_SCORE: i32 = 1_000_000;
+const COUNTERMOVE_SCORE: i32 = 900_000;
+// History scores are halved when one reaches this, so that recent cutoffs count for more.
+const HISTORY_MAX: i32 = 500_000;
+
+// What the search has learned about which moves cause cutoffs, used to search likely best moves
+// first so that the rest can be pruned.
+pub struct MoveOrdering {
+    // Two quiet moves per ply that recently caused a cutoff there, most recent first.
+    killers: Vec<[Option<ChessMove>; 2]>,
+    // How often a quiet move from one square to another has caused a cutoff, by color, weighted
+    // by the depth it was searched to.
+    history: Vec<[[i32; 64]; 64]>,
+    // The quiet move that last refuted each move, by the moving piece and the square it moved to.
+    countermoves: Vec<[Option<ChessMove>; 64]>,
+}
+
+impl MoveOrdering {
+    pub fn new(max_ply: usize) -> MoveOrdering {
+        MoveOrdering {
+            killers: vec![[None; 2]; max_ply + 1],
+            history: vec![[[0; 64]; 64]; 2],
+            countermoves: vec![[None; 64]; 12],
+        }
+    }
+
+    // Orders moves at ply to be searched, after the best move found by an earlier search and
+    // given the move that led to the position.
+    pub fn sort(
+        &self,
+        moves: &mut Vec<ChessMove>,
+        ply: usize,
+        hash_move: Option<ChessMove>,
+        previous: Option<ChessMove>,
+    ) {
+        let countermove =
+            previous.and_then(|m| self.countermoves[piece_index(m.moved_piece)][to(&m)]);
+        let mut scored: Vec<(i32, ChessMove)> = moves
+            .drain(..)
+            .map(|m| {
+                let score = if Some(m) == hash_move {
+                    HASH_MOVE_SCORE
+                } else if !is_quiet(&m) {
+                    CAPTURE_SCORE + mvv_lva(&m)
+                } else if Some(m) == self.killers[ply][0] {
+                    KILLER_SCORE + 1
+                } else if Some(m) == self.killers[ply][1] {
+                    KILLER_SCORE
+                } else if Some(m) == countermove {
+                    COUNTERMOVE_SCORE
+                } else {
+                    self.history[color_index(m.moved_piece.color)][from(&m)][to(&m)]
+                };
+                (score, m)
+            })
+            .collect();
+        scored.sort_by_key(|&(score, _)| Reverse(score));
+        moves.extend(scored.into_iter().map(|(_, m)| m));
+    }
+
+    // Remembers a move that caused a cutoff at ply when searched to depth. Captures and
+    // promotions already come early, so only quiet moves are remembered.
+    pub fn record_cutoff(
+        &mut self,
+        chess_move: ChessMove,
+        ply: usize,
+        depth: u32,
+        previous: Option<ChessMove>,
+    ) {
+        if !is_quiet(&chess_move) {
+            return;
+        }
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(chess_move) {
+            killers[1] = killers[0];
+            killers[0] = Some(chess_move);
+        }
+
+        let color = color_index(chess_move.moved_piece.color);
+        let entry = &mut self.history[color][from(&chess_move)][to(&chess_move)];
+        *entry += (depth * depth) as i32;
+        if *entry >= HISTORY_MAX {
+            for row in self.history[color].iter_mut() {
+                for score in row.iter_mut() {
+                    *score /= 2;
+                }
+            }
+        }
+
+        if let Some(m) = previous {
+            self.countermoves[piece_index(m.moved_piece)][to(&m)] = Some(chess_move);
+        }
+    }
+}
+
+// Orders captures with the most valuable victims first, taken by the least valuable attackers.
+pub fn sort_captures(moves: &mut [ChessMove]) {
+    moves.sort_by_key(|m| -mvv_lva(m));
+}
+
+// Scores a capture or promotion by the value it wins, less a little for the value of the piece
+// that is put at risk.
+fn mvv_lva(m: &ChessMove) -> i32 {
+    let victim = m.captured_piece.map_or(0, |piece| piece.value());
+    let promotion = m.promo_piece.map_or(0, |piece| piece.value());
+    (victim + promotion) * 100 - m.moved_piece.value()
+}
+
+// Whether the move neither captures nor promotes.
+pub fn is_quiet(m: &ChessMove) -> bool {
+    m.captured_piece.is_none() && m.promo_piece.is_none()
+}
+
+fn from(m: &ChessMove) -> usize {
+    m.o_rank * 8 + m.o_file
+}
+
+fn to(m: &ChessMove) -> usize {
+    m.n_rank * 8 + m.n_file
+}
+
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+fn piece_index(gp: GamePiece) -> usize {
+    let piece = match gp.piece {
+        Piece::Pawn => 0,
+        Piece::Rook => 1,
+        Piece::Knight => 2,
+        Piece::Bishop => 3,
+        Piece::Queen => 4,
+        Piece::King => 5,
+    };
+    color_index(gp.color) * 6 + piece
+}
@@ -0,0 +1,113 @@
+use super::MAX_PLY;
+use std::fmt;
+use std::ops::{Add, Neg, Sub};
+
+// The score of delivering mate at the root. Mates further away score less, so that the nearest
+// is preferred, but always more than any material balance.
+const MATE: i32 = 30_000;
+// Scores at least this far from zero are mates.
+const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+
+// An evaluation from the perspective of the side to move, in centipawns, or a forced mate.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Score(i32);
+
+impl Score {
+    pub const DRAW: Score = Score(0);
+    // Beyond any score a search can return, for an unbounded window.
+    pub const INFINITY: Score = Score(MATE + 1);
+
+    pub const fn cp(centipawns: i32) -> Score {
+        Score(centipawns)
+    }
+
+    // The score of the side to move being checkmated, ply moves into the line being searched.
+    pub fn mated_in(ply: usize) -> Score {
+        Score(-MATE + ply as i32)
+    }
+
+    pub fn centipawns(self) -> i32 {
+        self.0
+    }
+
+    pub fn is_mate(self) -> bool {
+        self.0.abs() >= MATE_BOUND
+    }
+
+    // How many moves until mate, negative if the side to move is mated, or None for other scores.
+    pub fn mate_in_moves(self) -> Option<i32> {
+        if !self.is_mate() {
+            return None;
+        }
+        let plies = MATE - self.0.abs();
+        Some(if self.0 > 0 {
+            (plies + 1) / 2
+        } else {
+            -plies / 2
+        })
+    }
+
+    // Mate scores count from the root, but the transposition table may give a position's score
+    // at another ply. They are stored counting from the position itself, and counted from the
+    // root again when read.
+    pub fn to_tt(self, ply: usize) -> Score {
+        self.shift_mate(ply as i32)
+    }
+
+    pub fn from_tt(self, ply: usize) -> Score {
+        self.shift_mate(-(ply as i32))
+    }
+
+    fn shift_mate(self, plies: i32) -> Score {
+        if !self.is_mate() {
+            self
+        } else if self.0 > 0 {
+            Score(self.0 + plies)
+        } else {
+            Score(self.0 - plies)
+        }
+    }
+}
+
+impl Neg for Score {
+    type Output = Score;
+
+    fn neg(self) -> Score {
+        Score(-self.0)
+    }
+}
+
+impl Add<i32> for Score {
+    type Output = Score;
+
+    fn add(self, centipawns: i32) -> Score {
+        Score(self.0 + centipawns)
+    }
+}
+
+impl Sub<i32> for Score {
+    type Output = Score;
+
+    fn sub(self, centipawns: i32) -> Score {
+        Score(self.0 - centipawns)
+    }
+}
+
+// Scores are written in pawns with a sign, e.g. +1.25, or as the moves to mate, e.g. #5 or #-3.
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mate_in_moves() {
+            Some(moves) => write!(f, "#{}", moves),
+            None => {
+                let sign = if self.0 < 0 { "-" } else { "+" };
+                write!(
+                    f,
+                    "{}{}.{:02}",
+                    sign,
+                    self.0.abs() / 100,
+                    self.0.abs() % 100
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use super::score::Score;
+use crate::game::chess_move::ChessMove;
+use crate::game::color::Color;
+use crate::game::game_piece::{GamePiece, Piece};
+use std::mem;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// The size of the transposition table when none is asked for, in megabytes.
+pub const DEFAULT_HASH_MB: usize = 16;
+// The largest transposition table that may be asked for, in megabytes.
+pub const MAX_HASH_MB: usize = 4096;
+
+// How a stored score relates to the true score of the position. Alpha-beta only learns the exact
+// score when it falls inside the window; otherwise it learns a bound.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Bound {
+    Exact,
+    // The score is at least this, as the search failed high.
+    Lower,
+    // The score is at most this, as the search failed low.
+    Upper,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Entry {
+    pub key: u64,
+    pub depth: u32,
+    pub bound: Bound,
+    // Mates are counted from the stored position rather than the root of the search.
+    pub score: Score,
+    pub best_move: Option<ChessMove>,
+}
+
+// TranspositionTable remembers the results of searching positions by their Zobrist key, so that a
+// position reached again, by transposition or in the next iteration, needn't be searched again.
+// It is a fixed number of slots, each position having one slot it may be stored in. It may be
+// shared by threads searching at once.
+#[derive(Debug)]
+pub struct TranspositionTable {
+    entries: Vec<Slot>,
+}
+
+// A slot holds an entry packed into one word, and the position's key XORed with that word. Threads
+// read and write slots without locking, so a slot may be read while half written, or written by
+// two threads at once. The words then don't match the key and the slot reads as empty.
+#[derive(Debug, Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+// Marks a slot's data as holding an entry, as an empty slot's is zero.
+const OCCUPIED: u64 = 1 << 63;
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MB)
+    }
+}
+
+impl TranspositionTable {
+    pub fn new(megabytes: usize) -> TranspositionTable {
+        let slots = (megabytes * 1024 * 1024 / mem::size_of::<Slot>()).max(1);
+        TranspositionTable {
+            entries: (0..slots).map(|_| Slot::default()).collect(),
+        }
+    }
+
+    pub fn clear(&self) {
+        for slot in self.entries.iter() {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    // The memory used by the table's slots, in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        self.entries.len() * mem::size_of::<Slot>()
+    }
+
+    // Estimates how full the table is, in thousandths, from the first thousand slots.
+    pub fn hashfull(&self) -> usize {
+        let sample = &self.entries[..self.entries.len().min(1000)];
+        let used = sample
+            .iter()
+            .filter(|slot| slot.data.load(Ordering::Relaxed) & OCCUPIED != 0)
+            .count();
+        used * 1000 / sample.len()
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        let slot = &self.entries[self.index(key)];
+        let data = slot.data.load(Ordering::Relaxed);
+        if data & OCCUPIED == 0 || slot.key.load(Ordering::Relaxed) ^ data != key {
+            return None;
+        }
+        Some(unpack(key, data))
+    }
+
+    // Stores the result of a search. Another position in the slot is replaced, but a deeper
+    // search of the same position is kept.
+    pub fn store(
+        &self,
+        key: u64,
+        depth: u32,
+        bound: Bound,
+        score: Score,
+        best_move: Option<ChessMove>,
+    ) {
+        if self
+            .probe(key)
+            .is_some_and(|existing| existing.depth > depth)
+        {
+            return;
+        }
+        let data = pack(&Entry {
+            key,
+            depth,
+            bound,
+            score,
+            best_move,
+        });
+        let slot = &self.entries[self.index(key)];
+        slot.key.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+}
+
+// Packs an entry, less its key, into a word: the score in the low 16 bits, then the depth, the
+// bound and the best move.
+fn pack(entry: &Entry) -> u64 {
+    let score = u64::from(entry.score.centipawns() as i16 as u16);
+    let depth = u64::from(entry.depth.min(u32::from(u8::MAX)));
+    let bound = match entry.bound {
+        Bound::Exact => 0,
+        Bound::Lower => 1,
+        Bound::Upper => 2,
+    };
+    let best_move = entry.best_move.map_or(0, |m| pack_move(&m));
+    OCCUPIED | best_move << 26 | bound << 24 | depth << 16 | score
+}
+
+fn unpack(key: u64, data: u64) -> Entry {
+    let bound = match (data >> 24) & 0b11 {
+        0 => Bound::Exact,
+        1 => Bound::Lower,
+        _ => Bound::Upper,
+    };
+    Entry {
+        key,
+        depth: ((data >> 16) & 0xff) as u32,
+        bound,
+        score: Score::cp(i32::from(data as u16 as i16)),
+        best_move: unpack_move(data >> 26),
+    }
+}
+
+// Packs a move into 24 bits: a bit marking that there is one, the squares it moves from and to,
+// and the pieces it moves, captures and promotes to, each numbered from 1 with 0 for none.
+fn pack_move(m: &ChessMove) -> u64 {
+    let from = (m.o_rank * 8 + m.o_file) as u64;
+    let to = (m.n_rank * 8 + m.n_file) as u64;
+    let moved = game_piece_code(m.moved_piece);
+    let captured = m.captured_piece.map_or(0, game_piece_code);
+    let promo = m.promo_piece.map_or(0, piece_code);
+    1 | from << 1 | to << 7 | moved << 13 | captured << 17 | promo << 21
+}
+
+fn unpack_move(bits: u64) -> Option<ChessMove> {
+    if bits & 1 == 0 {
+        return None;
+    }
+    let from = ((bits >> 1) & 0x3f) as usize;
+    let to = ((bits >> 7) & 0x3f) as usize;
+    Some(ChessMove::new(
+        game_piece_from_code((bits >> 13) & 0xf)?,
+        from % 8,
+        from / 8,
+        to % 8,
+        to / 8,
+        piece_from_code((bits >> 21) & 0x7),
+        game_piece_from_code((bits >> 17) & 0xf),
+    ))
+}
+
+const PIECES: [Piece; 6] = [
+    Piece::Pawn,
+    Piece::Rook,
+    Piece::Knight,
+    Piece::Bishop,
+    Piece::Queen,
+    Piece::King,
+];
+
+fn piece_code(piece: Piece) -> u64 {
+    match piece {
+        Piece::Pawn => 1,
+        Piece::Rook => 2,
+        Piece::Knight => 3,
+        Piece::Bishop => 4,
+        Piece::Queen => 5,
+        Piece::King => 6,
+    }
+}
+
+fn piece_from_code(code: u64) -> Option<Piece> {
+    PIECES.get((code as usize).checked_sub(1)?).copied()
+}
+
+// Black's pieces are numbered after White's.
+fn game_piece_code(gp: GamePiece) -> u64 {
+    match gp.color {
+        Color::White => piece_code(gp.piece),
+        Color::Black => piece_code(gp.piece) + 6,
+    }
+}
+
+fn game_piece_from_code(code: u64) -> Option<GamePiece> {
+    match code {
+        0 => None,
+        1..=6 => Some(GamePiece::new(piece_from_code(code)?, Color::White)),
+        _ => Some(GamePiece::new(piece_from_code(code - 6)?, Color::Black)),
+    }
+}
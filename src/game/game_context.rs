@@ -1,42 +1,88 @@
 use super::chess_move::ChessMove;
-
 use super::color::Color;
-use super::game_piece::Piece;
-use super::position::Position;
-use super::tree::Node;
-use std::mem;
+use super::game_status::GameStatus;
+use super::pgn;
+use super::position::{FenError, Position, UndoState};
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct GameContext {
     pub position: Position,
+    // The position the game started from, from which chess_moves can be replayed.
+    pub start_position: Position,
     pub chess_moves: Vec<ChessMove>,
-    pub tree: Node,
+    // What is needed to take back each of chess_moves, most recent last.
+    pub undo_states: Vec<UndoState>,
+    // The Zobrist key of every position in the game, the current position last.
+    pub position_keys: Vec<u64>,
+    // Whether the game is still being played, brought up to date after each move.
+    pub status: GameStatus,
+    // PGN tags describing the game, e.g. ("White", "Rob Chess").
+    pub tags: Vec<(String, String)>,
+}
+
+impl Default for GameContext {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GameContext {
     pub fn new() -> GameContext {
-        GameContext {
+        let tags: Vec<(String, String)> = pgn::SEVEN_TAG_ROSTER
+            .iter()
+            .map(|name| (name.to_string(), "?".to_owned()))
+            .collect();
+
+        let mut g = GameContext {
             position: Position::new(),
+            start_position: Position::new(),
             chess_moves: Vec::<ChessMove>::new(),
-            tree: Node::new(None, None),
-        }
+            undo_states: Vec::<UndoState>::new(),
+            position_keys: vec![Position::new().hash],
+            status: GameStatus::Ongoing,
+            tags,
+        };
+        g.set_tag("Date", &pgn::pgn_date_today());
+        g.set_tag("Result", "*");
+        g
     }
 
-    // Makes a move in the game and records it.
+    // Starts a game from the position described by a FEN string.
+    pub fn from_fen(fen: &str) -> Result<GameContext, FenError> {
+        let mut g = GameContext::new();
+        g.position = Position::from_fen(fen)?;
+        g.start_position = g.position.clone();
+        g.position_keys = vec![g.position.hash];
+        g.status = GameStatus::of(&g);
+        Ok(g)
+    }
+
+    // Makes a move in the game and records it. Illegal moves are rejected.
     pub fn make_move(&mut self, chess_move: ChessMove) -> Result<(), ()> {
-        self.position.make_move(&chess_move)?;
+        if !self.position.is_legal(&chess_move) {
+            return Err(());
+        }
+        let undo = self.position.make_move(&chess_move)?;
         self.chess_moves.push(chess_move);
+        self.undo_states.push(undo);
+        self.position_keys.push(self.position.hash);
+        self.status = GameStatus::of(self);
 
-        // println!("{:?}", self.tree.children);
-        // Discard unused parts of the tree.
-        let children = &mut self.tree.children;
-        for node in children.into_iter() {
-            if node.last_move == Some(chess_move) {
-                self.tree.children = mem::replace(&mut node.children, vec![]);
-                break;
-            }
-        }
+        Ok(())
+    }
+
+    // Takes back the last move of the game, returning it. Fails if no moves have been made.
+    pub fn undo_move(&mut self) -> Result<ChessMove, ()> {
+        let chess_move = self.chess_moves.pop().ok_or(())?;
+        let undo = self.undo_states.pop().ok_or(())?;
+        self.position.undo_move(&chess_move, undo)?;
+        self.position_keys.pop();
+        self.status = GameStatus::of(self);
+        Ok(chess_move)
+    }
 
-        return Ok(());
+    // Ends the game with the given color resigning.
+    pub fn resign(&mut self, color: Color) {
+        self.status = GameStatus::Resignation(color);
     }
 }
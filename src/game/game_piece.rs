@@ -17,15 +17,16 @@ pub enum Piece {
 }
 
 impl Piece {
-    // Value returns the value of the piece. For now, the value is the classical chess piece value.
+    // Value returns the value of the piece in centipawns. For now, the value is the classical chess
+    // piece value. The king can't be traded, so it counts for nothing.
     pub fn value(&self) -> i32 {
         match self {
-            Piece::Pawn => 1,
-            Piece::Rook => 5,
-            Piece::Knight => 3,
-            Piece::Bishop => 3,
-            Piece::Queen => 9,
-            Piece::King => 200,
+            Piece::Pawn => 100,
+            Piece::Rook => 500,
+            Piece::Knight => 300,
+            Piece::Bishop => 300,
+            Piece::Queen => 900,
+            Piece::King => 0,
         }
     }
 }
@@ -60,15 +61,17 @@ impl FromStr for Piece {
     type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Either case is accepted, as promotion pieces are written in lowercase in long algebraic
+        // notation.
         match s {
-            "P" => return Ok(Piece::Pawn),
-            "R" => return Ok(Piece::Rook),
-            "N" => return Ok(Piece::Knight),
-            "b" => return Ok(Piece::Bishop),
-            "Q" => return Ok(Piece::Queen),
-            "K" => return Ok(Piece::King),
-            _ => return Err(ParseError {}),
-        };
+            "P" | "p" => Ok(Piece::Pawn),
+            "R" | "r" => Ok(Piece::Rook),
+            "N" | "n" => Ok(Piece::Knight),
+            "B" | "b" => Ok(Piece::Bishop),
+            "Q" | "q" => Ok(Piece::Queen),
+            "K" | "k" => Ok(Piece::King),
+            _ => Err(ParseError {}),
+        }
     }
 }
 
@@ -87,6 +90,27 @@ impl GamePiece {
     pub fn value(&self) -> i32 {
         self.piece.value()
     }
+
+    // Returns the piece for a FEN character. Uppercase letters are White and lowercase are Black.
+    pub fn from_char(c: char) -> Option<GamePiece> {
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        Piece::from_str(&c.to_string())
+            .ok()
+            .map(|piece| GamePiece::new(piece, color))
+    }
+
+    // Returns the FEN character for the piece.
+    pub fn to_char(self) -> char {
+        let c = self.piece.to_string().chars().next().unwrap_or('?');
+        match self.color {
+            Color::White => c,
+            Color::Black => c.to_ascii_lowercase(),
+        }
+    }
 }
 
 impl fmt::Display for GamePiece {
@@ -0,0 +1,133 @@
+use super::*;
+use crate::game::chess_move::ChessMove;
+use crate::game::game_context::GameContext;
+
+fn play(g: &mut GameContext, moves: &[&str]) {
+    for m in moves {
+        let chess_move = ChessMove::from_san(m, &g.position).unwrap();
+        g.make_move(chess_move).unwrap();
+    }
+}
+
+#[test]
+fn new_games_are_ongoing() {
+    let g = GameContext::new();
+    assert_eq!(g.status, GameStatus::Ongoing);
+    assert!(!g.status.is_over());
+    assert_eq!(g.result(), "*");
+}
+
+#[test]
+fn checkmate_ends_the_game() {
+    let mut g = GameContext::new();
+    play(&mut g, &["f3", "e5", "g4", "Qh4#"]);
+    assert_eq!(g.status, GameStatus::Checkmate(Color::Black));
+    assert_eq!(g.result(), "0-1");
+    assert_eq!(g.status.to_string(), "Black wins by checkmate");
+
+    g.undo_move().unwrap();
+    assert_eq!(g.status, GameStatus::Ongoing);
+}
+
+#[test]
+fn stalemate_is_a_draw() {
+    let mut g = GameContext::from_fen("7k/8/6Q1/8/8/8/8/K7 w - - 0 1").unwrap();
+    assert_eq!(g.status, GameStatus::Ongoing);
+    play(&mut g, &["Qf7"]);
+    assert_eq!(g.status, GameStatus::Stalemate);
+    assert_eq!(g.result(), "1/2-1/2");
+}
+
+#[test]
+fn positions_can_start_over() {
+    let g = GameContext::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+    assert_eq!(g.status, GameStatus::Checkmate(Color::White));
+}
+
+#[test]
+fn fifty_moves_without_progress_is_a_draw() {
+    let mut g = GameContext::from_fen("4k3/8/8/8/8/8/8/4K2R w - - 99 80").unwrap();
+    assert_eq!(g.status, GameStatus::Ongoing);
+    play(&mut g, &["Rh2"]);
+    assert_eq!(g.status, GameStatus::DrawByRule(DrawRule::FiftyMoves));
+    assert_eq!(g.result(), "1/2-1/2");
+}
+
+#[test]
+fn checkmate_on_the_hundredth_half_move_stands() {
+    let mut g = GameContext::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80").unwrap();
+    play(&mut g, &["Ra8#"]);
+    assert_eq!(g.status, GameStatus::Checkmate(Color::White));
+}
+
+#[test]
+fn resignation_ends_the_game() {
+    let mut g = GameContext::new();
+    play(&mut g, &["e4"]);
+    g.resign(Color::Black);
+    assert_eq!(g.status, GameStatus::Resignation(Color::Black));
+    assert!(g.status.is_over());
+    assert_eq!(g.result(), "1-0");
+    assert_eq!(g.status.to_string(), "Black resigns, White wins");
+}
+
+#[test]
+fn seventy_five_moves_without_progress_ends_the_game() {
+    let mut g = GameContext::from_fen("4k3/8/8/8/8/8/8/4K2R w - - 149 110").unwrap();
+    assert_eq!(g.status, GameStatus::DrawByRule(DrawRule::FiftyMoves));
+    play(&mut g, &["Rh2"]);
+    assert_eq!(g.status, GameStatus::DrawByRule(DrawRule::SeventyFiveMoves));
+}
+
+#[test]
+fn threefold_and_fivefold_repetition() {
+    let shuffle = ["Nf3", "Nf6", "Ng1", "Ng8"];
+    let mut g = GameContext::new();
+    play(&mut g, &shuffle);
+    assert_eq!(g.status, GameStatus::Ongoing);
+    play(&mut g, &shuffle[..3]);
+    assert_eq!(g.status, GameStatus::Ongoing);
+    play(&mut g, &shuffle[3..]);
+    assert_eq!(
+        g.status,
+        GameStatus::DrawByRule(DrawRule::ThreefoldRepetition)
+    );
+    assert_eq!(g.status.to_string(), "Drawn by threefold repetition");
+
+    g.undo_move().unwrap();
+    assert_eq!(g.status, GameStatus::Ongoing);
+    play(&mut g, &shuffle[3..]);
+    play(&mut g, &shuffle);
+    assert_eq!(
+        g.status,
+        GameStatus::DrawByRule(DrawRule::ThreefoldRepetition)
+    );
+    play(&mut g, &shuffle);
+    assert_eq!(
+        g.status,
+        GameStatus::DrawByRule(DrawRule::FivefoldRepetition)
+    );
+}
+
+#[test]
+fn repetitions_are_counted_since_the_last_irreversible_move() {
+    let mut g = GameContext::new();
+    play(&mut g, &["Nf3", "Nf6", "Ng1", "Ng8", "e4", "e5"]);
+    play(&mut g, &["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1"]);
+    assert_eq!(g.status, GameStatus::Ongoing);
+    play(&mut g, &["Ng8"]);
+    assert_eq!(
+        g.status,
+        GameStatus::DrawByRule(DrawRule::ThreefoldRepetition)
+    );
+}
+
+#[test]
+fn insufficient_material_is_a_draw() {
+    let mut g = GameContext::from_fen("4k3/8/8/8/8/8/3r4/4KB2 w - - 0 1").unwrap();
+    play(&mut g, &["Kxd2"]);
+    assert_eq!(
+        g.status,
+        GameStatus::DrawByRule(DrawRule::InsufficientMaterial)
+    );
+}
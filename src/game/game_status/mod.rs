@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod game_status_tests;
+
+use super::color::Color;
+use super::game_context::GameContext;
+use std::fmt;
+
+// The rules under which a game is drawn without either side agreeing to it. The fifty-move rule
+// and threefold repetition are draws a player may claim, which are claimed on their behalf here.
+// The others end the game outright, and take their place once a game continues past a claim.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DrawRule {
+    // A hundred half-moves have been played without a capture or a pawn move.
+    FiftyMoves,
+    // A hundred and fifty half-moves have been played without a capture or a pawn move.
+    SeventyFiveMoves,
+    ThreefoldRepetition,
+    FivefoldRepetition,
+    // Neither side can ever checkmate.
+    InsufficientMaterial,
+}
+
+// GameStatus describes whether a game is still being played and, if not, how it ended.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GameStatus {
+    Ongoing,
+    // The winning color.
+    Checkmate(Color),
+    Stalemate,
+    DrawByRule(DrawRule),
+    // The color that resigned.
+    Resignation(Color),
+}
+
+impl GameStatus {
+    // Works out the status of a game from its current position and the positions before it.
+    pub fn of(g: &GameContext) -> GameStatus {
+        let mut p = g.position.clone();
+        let color = p.side_to_move;
+        if p.get_moves().is_empty() {
+            return match p.in_check(color) {
+                true => GameStatus::Checkmate(color.opp_color()),
+                false => GameStatus::Stalemate,
+            };
+        }
+        match draw_rule(g) {
+            Some(rule) => GameStatus::DrawByRule(rule),
+            None => GameStatus::Ongoing,
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        *self != GameStatus::Ongoing
+    }
+
+    // The PGN result token for a game that is over, or None while it is still being played.
+    pub fn result(&self) -> Option<&'static str> {
+        match self {
+            GameStatus::Ongoing => None,
+            GameStatus::Checkmate(Color::White) | GameStatus::Resignation(Color::Black) => {
+                Some("1-0")
+            }
+            GameStatus::Checkmate(Color::Black) | GameStatus::Resignation(Color::White) => {
+                Some("0-1")
+            }
+            GameStatus::Stalemate | GameStatus::DrawByRule(_) => Some("1/2-1/2"),
+        }
+    }
+}
+
+// Finds the rule, if any, that draws a game in which the side to move has a legal move. Rules that
+// end the game outright come before those that are claimed.
+fn draw_rule(g: &GameContext) -> Option<DrawRule> {
+    let halfmove_clock = g.position.halfmove_clock;
+    let repetitions = count_repetitions(&g.position_keys, halfmove_clock);
+    if repetitions >= 5 {
+        Some(DrawRule::FivefoldRepetition)
+    } else if halfmove_clock >= 150 {
+        Some(DrawRule::SeventyFiveMoves)
+    } else if g.position.has_insufficient_material() {
+        Some(DrawRule::InsufficientMaterial)
+    } else if repetitions >= 3 {
+        Some(DrawRule::ThreefoldRepetition)
+    } else if halfmove_clock >= 100 {
+        Some(DrawRule::FiftyMoves)
+    } else {
+        None
+    }
+}
+
+// Counts how many times the last of keys has occurred, itself included. Only the positions since
+// the last capture or pawn move can repeat, and only those with the same side to move.
+pub fn count_repetitions(keys: &[u64], halfmove_clock: u32) -> usize {
+    let current = match keys.last() {
+        Some(key) => key,
+        None => return 0,
+    };
+    keys.iter()
+        .rev()
+        .take(halfmove_clock as usize + 1)
+        .step_by(2)
+        .filter(|&key| key == current)
+        .count()
+}
+
+impl fmt::Display for DrawRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DrawRule::FiftyMoves => write!(f, "the fifty-move rule"),
+            DrawRule::SeventyFiveMoves => write!(f, "the seventy-five-move rule"),
+            DrawRule::ThreefoldRepetition => write!(f, "threefold repetition"),
+            DrawRule::FivefoldRepetition => write!(f, "fivefold repetition"),
+            DrawRule::InsufficientMaterial => write!(f, "insufficient material"),
+        }
+    }
+}
+
+impl fmt::Display for GameStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameStatus::Ongoing => write!(f, "The game is in progress"),
+            GameStatus::Checkmate(winner) => write!(f, "{:?} wins by checkmate", winner),
+            GameStatus::Stalemate => write!(f, "Drawn by stalemate"),
+            GameStatus::DrawByRule(rule) => write!(f, "Drawn by {}", rule),
+            GameStatus::Resignation(color) => {
+                write!(f, "{:?} resigns, {:?} wins", color, color.opp_color())
+            }
+        }
+    }
+}
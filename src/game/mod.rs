@@ -1,28 +1,92 @@
-mod chess_move;
-mod color;
-mod engine;
-mod game_context;
-mod game_piece;
-mod position;
-mod tree;
-
-use chess_move::ChessMove;
+pub mod chess_move;
+pub mod color;
+pub mod engine;
+pub mod game_context;
+pub mod game_piece;
+pub mod game_status;
+pub mod pgn;
+pub mod position;
+pub mod uci;
+pub mod xboard;
+
+use chess_move::{ChessMove, ParseMoveError};
 use color::Color;
+use engine::transposition::TranspositionTable;
 use game_context::GameContext;
-use position::Position;
+use position::{FenError, Position, START_FEN};
+use std::fs;
 use std::io;
+use std::time::Instant;
 
 pub fn start_user_session() {
-    println!(
-        "Welcome to Rob Chess! When entering moves, please use long algebraic chess notation."
-    );
+    println!("Welcome to Rob Chess! Moves may be entered in long algebraic notation, e.g. e2e4 or e7e8q, or in standard algebraic notation, e.g. Nf3, exd5, O-O or e8=Q.");
 
-    let mut game_ctx = GameContext::new();
+    let mut game_ctx = prompt_game();
     let color = prompt_color();
+    let (white, black) = match color {
+        Color::White => ("Human", "Rob Chess"),
+        Color::Black => ("Rob Chess", "Human"),
+    };
+    game_ctx.set_tag("White", white);
+    game_ctx.set_tag("Black", black);
+    println!("The game can be saved at any time by entering 'save <file>' at the move prompt, and 'resign' ends it.");
 
     println!("{}", game_ctx.position);
 
-    game_loop(color, color, &mut game_ctx);
+    game_loop(color, &mut game_ctx);
+}
+
+// Prints the perft count of each move from the position, followed by the total, in the format
+// other engines use so the output can be compared line by line.
+pub fn run_perft(depth: u32, fen: &str) -> Result<(), FenError> {
+    let mut p = Position::from_fen(fen)?;
+    let start = Instant::now();
+    let divided = p.divide(depth);
+    for (chess_move, nodes) in &divided {
+        println!("{}: {}", chess_move, nodes);
+    }
+    let nodes: u64 = match depth {
+        0 => 1,
+        _ => divided.iter().map(|(_, nodes)| nodes).sum(),
+    };
+    println!("\nNodes searched: {}", nodes);
+    println!("Time: {} ms", start.elapsed().as_millis());
+    Ok(())
+}
+
+fn prompt_game() -> GameContext {
+    println!("Enter a FEN to start from, 'load <file>' to continue a PGN game, or press enter for the standard starting position.");
+    let mut input = String::new();
+    if let Err(err) = io::stdin().read_line(&mut input) {
+        println!("Something went wrong reading: {}", err);
+        return prompt_game();
+    }
+    if let Some(path) = input.trim().strip_prefix("load ") {
+        let loaded = fs::read_to_string(path.trim())
+            .map_err(|err| err.to_string())
+            .and_then(|pgn| GameContext::from_pgn(&pgn).map_err(|err| err.to_string()));
+        return match loaded {
+            Ok(g) => {
+                println!("Loaded {}", g.movetext());
+                g
+            }
+            Err(err) => {
+                println!("The game could not be loaded: {}. Please try again.", err);
+                prompt_game()
+            }
+        };
+    }
+    let fen = match input.trim() {
+        "" => START_FEN,
+        fen => fen,
+    };
+    match GameContext::from_fen(fen) {
+        Ok(g) => g,
+        Err(err) => {
+            println!("The FEN could not be read: {}. Please try again.", err);
+            prompt_game()
+        }
+    }
 }
 
 fn prompt_color() -> Color {
@@ -30,8 +94,8 @@ fn prompt_color() -> Color {
     let mut input = String::new();
     match io::stdin().read_line(&mut input) {
         Ok(_) => match input.trim() {
-            "w" => return Color::White,
-            "b" => return Color::Black,
+            "w" => Color::White,
+            "b" => Color::Black,
             _ => {
                 println!("Unrecognized input was: {}. Please try again.", input);
                 prompt_color()
@@ -44,49 +108,141 @@ fn prompt_color() -> Color {
     }
 }
 
-fn get_move(p: &Position) -> ChessMove {
+// Asks the player for their move. Returns None if they resign or stop playing.
+fn get_move(g: &mut GameContext) -> Option<ChessMove> {
     println!("Move: ");
     let move_str = match read_move() {
         Ok(input) => input,
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return None,
         Err(err) => {
             // Abort assignment and start the process over.
             println!("Error reading input: {}", err);
-            return get_move(p);
+            return get_move(g);
         }
     };
 
-    match ChessMove::from_algebraic(&move_str, p) {
+    if let Some(path) = move_str.trim().strip_prefix("save ") {
+        save_game(g, path.trim());
+        return get_move(g);
+    }
+    if move_str.trim() == "resign" {
+        let color = g.position.side_to_move;
+        g.resign(color);
+        return None;
+    }
+
+    let p = &mut g.position;
+
+    // Long algebraic notation is tried first, as standard algebraic notation requires the move
+    // to be legal to be understood at all.
+    let chess_move = match ChessMove::from_algebraic(&move_str, p)
+        .or_else(|_| ChessMove::from_san(&move_str, p))
+    {
         Ok(res) => res,
-        Err(err) => {
-            println!("The move entered could not be understood. Please enter a move in long algrebraic chess notation.");
-            return get_move(p);
+        Err(ParseMoveError::Ambiguous) => {
+            println!(
+                "The move entered is ambiguous. Please name the file or rank the piece moves from."
+            );
+            return get_move(g);
+        }
+        Err(_) => {
+            println!("The move entered could not be understood or is not legal. Please enter a move in long or standard algebraic chess notation.");
+            return get_move(g);
         }
+    };
+
+    if !p.is_legal(&chess_move) {
+        println!("{} is not a legal move. Please try again.", chess_move);
+        return get_move(g);
     }
+    Some(chess_move)
 }
 
 fn read_move() -> Result<String, io::Error> {
     let mut input = String::new();
-    io::stdin().read_line(&mut input).map(|_| input)
+    match io::stdin().read_line(&mut input)? {
+        0 => Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+        _ => Ok(input),
+    }
 }
 
-fn game_loop(color: Color, player_color: Color, g: &mut GameContext) {
-    let opp_color = color.opp_color();
-
-    let chess_move = if color == player_color {
+fn game_loop(player_color: Color, g: &mut GameContext) {
+    let tt = TranspositionTable::default();
+    while !g.status.is_over() {
+        let chess_move = if g.position.side_to_move == player_color {
+            println!(
+                "think_depth start: I think your moves are {}",
+                g.position
+                    .get_moves()
+                    .iter()
+                    .fold(String::new(), |acc, &arg| acc
+                        + ", "
+                        + &arg.to_san(&g.position))
+            );
+            match get_move(g) {
+                Some(chess_move) => chess_move,
+                None => break,
+            }
+        } else {
+            engine::think(g, &tt)
+        };
         println!(
-            "think_depth start: I think your moves are {}",
-            g.position
-                .get_moves(color)
-                .iter()
-                .fold(String::new(), |acc, &arg| acc + ", " + &arg.to_string())
+            "{:?} plays {}",
+            chess_move.moved_piece.color,
+            chess_move.to_san(&g.position)
         );
-        get_move(&g.position)
-    } else {
-        engine::think(g, color)
-    };
-    g.make_move(chess_move)
-        .expect("Something went wrong processing the move\n");
-    println!("Moves so far: {:?}\n", g.chess_moves);
-    println!("{}", g.position);
-    game_loop(opp_color, player_color, g);
+        g.make_move(chess_move)
+            .expect("Something went wrong processing the move\n");
+        println!("Moves so far: {}\n", g.movetext());
+        println!("{}", g.position);
+        println!("FEN: {}", g.position.to_fen());
+        report_check(&g.position);
+    }
+
+    if g.status.is_over() {
+        println!("{}. The result is {}.", g.status, g.result());
+        println!("Moves: {} {}", g.movetext(), g.result());
+        offer_save(g);
+    }
+}
+
+// Lets the player save a finished game, as many times as they like, until they press enter.
+fn offer_save(g: &GameContext) {
+    println!("Enter 'save <file>' to save the game, or press enter to finish.");
+    loop {
+        let input = match read_move() {
+            Ok(input) => input,
+            Err(_) => return,
+        };
+        match input.trim() {
+            "" => return,
+            command => match command.strip_prefix("save ") {
+                Some(path) => save_game(g, path.trim()),
+                None => println!("Unrecognized input was: {}. Please try again.", command),
+            },
+        }
+    }
+}
+
+fn save_game(g: &GameContext, path: &str) {
+    match g.write_pgn(path) {
+        Ok(()) => println!("Saved the game to {}.", path),
+        Err(err) => println!("The game could not be saved: {}", err),
+    }
+}
+
+// Lets the player know when the side to move has been put in check, and by which pieces.
+fn report_check(p: &Position) {
+    let color = p.side_to_move;
+    if !p.in_check(color) {
+        return;
+    }
+    let king_square = p.get_king_square(color);
+    let checkers = p
+        .attackers_of(&king_square)
+        .iter()
+        .filter(|s| p.board[s.rank][s.file].map(|gp| gp.color) != Some(color))
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+    println!("{:?} is in check from {}.", color, checkers.join(", "));
 }
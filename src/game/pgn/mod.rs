@@ -0,0 +1,144 @@
+#[cfg(test)]
+mod pgn_tests;
+mod reader;
+
+pub use reader::{parse_games, PgnError, PgnGame, PgnMove};
+
+use super::color::Color;
+use super::game_context::GameContext;
+use super::position::START_FEN;
+use std::fs;
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// The tags every PGN game must carry, in the order they must appear.
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+// PGN import format asks for movetext lines of no more than 79 characters.
+const MAX_LINE_LENGTH: usize = 79;
+
+impl GameContext {
+    // Returns the value of a tag, if it has been set.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    // Sets a tag, replacing any existing value.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some(tag) => tag.1 = value.to_owned(),
+            None => self.tags.push((name.to_owned(), value.to_owned())),
+        }
+    }
+
+    // Returns the PGN result token for the game. A game that has ended is scored from its status;
+    // otherwise the Result tag is used, which is "*" while the game is in progress.
+    pub fn result(&self) -> &str {
+        match self.status.result() {
+            Some(result) => result,
+            None => self.tag("Result").unwrap_or("*"),
+        }
+    }
+
+    // Returns the moves of the game in SAN with move numbers, e.g. "1. e4 e5 2. Nf3".
+    pub fn movetext(&self) -> String {
+        self.movetext_tokens().join(" ")
+    }
+
+    fn movetext_tokens(&self) -> Vec<String> {
+        let mut tokens = Vec::<String>::with_capacity(self.chess_moves.len() * 3 / 2);
+        let mut p = self.start_position.clone();
+        for (i, chess_move) in self.chess_moves.iter().enumerate() {
+            if p.side_to_move == Color::White {
+                tokens.push(format!("{}.", p.fullmove_number));
+            } else if i == 0 {
+                tokens.push(format!("{}...", p.fullmove_number));
+            }
+            tokens.push(chess_move.to_san(&p));
+            p.make_move(chess_move)
+                .expect("Recorded moves should replay from the start position.");
+        }
+        tokens
+    }
+
+    // Returns the game in PGN export format: the Seven Tag Roster followed by any other tags, then
+    // the movetext terminated by the result.
+    pub fn to_pgn(&self) -> String {
+        let result = self.result().to_owned();
+        let mut pgn = String::new();
+
+        for name in SEVEN_TAG_ROSTER.iter() {
+            let value = match *name {
+                "Result" => result.as_str(),
+                _ => self.tag(name).unwrap_or("?"),
+            };
+            pgn += &format_tag(name, value);
+        }
+        let start_fen = self.start_position.to_fen();
+        if start_fen != START_FEN {
+            pgn += &format_tag("SetUp", "1");
+            pgn += &format_tag("FEN", &start_fen);
+        }
+        for (name, value) in self.tags.iter() {
+            let exported =
+                SEVEN_TAG_ROSTER.contains(&name.as_str()) || name == "SetUp" || name == "FEN";
+            if !exported {
+                pgn += &format_tag(name, value);
+            }
+        }
+        pgn.push('\n');
+
+        let mut tokens = self.movetext_tokens();
+        tokens.push(result);
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > MAX_LINE_LENGTH {
+                pgn += &line;
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line += &token;
+        }
+        pgn += &line;
+        pgn += "\n\n";
+        pgn
+    }
+
+    // Writes the game in PGN to a file, replacing its contents.
+    pub fn write_pgn(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_pgn())
+    }
+}
+
+fn format_tag(name: &str, value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("[{} \"{}\"]\n", name, escaped)
+}
+
+// Returns today's date in the PGN YYYY.MM.DD format.
+pub fn pgn_date_today() -> String {
+    let secs = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(_) => return "????.??.??".to_owned(),
+    };
+
+    // Convert days since the epoch to a civil date, following Howard Hinnant's days_from_civil
+    // inverse.
+    let z = secs / 86_400 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
@@ -0,0 +1,192 @@
+use crate::game::chess_move::ChessMove;
+use crate::game::game_context::GameContext;
+use crate::game::pgn::{parse_games, PgnError};
+
+fn play(g: &mut GameContext, sans: &[&str]) {
+    for san in sans {
+        let chess_move = ChessMove::from_san(san, &g.position).unwrap();
+        g.make_move(chess_move).unwrap();
+    }
+}
+
+#[test]
+fn movetext_numbers_moves() {
+    let mut g = GameContext::new();
+    play(&mut g, &["e4", "e5", "Nf3"]);
+
+    assert_eq!(g.movetext(), "1. e4 e5 2. Nf3");
+}
+
+#[test]
+fn movetext_numbers_moves_from_black_to_move() {
+    let mut g = GameContext::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 12").unwrap();
+    play(&mut g, &["Kd7", "e4"]);
+
+    assert_eq!(g.movetext(), "12... Kd7 13. e4");
+}
+
+#[test]
+fn to_pgn_writes_seven_tag_roster_and_result() {
+    let mut g = GameContext::new();
+    g.set_tag("White", "Rob Chess");
+    g.set_tag("Black", "A \"quoted\" name");
+    g.set_tag("Date", "2020.01.02");
+    g.set_tag("Annotator", "Test");
+    play(&mut g, &["f3", "e5", "g4", "Qh4#"]);
+
+    let expected = "[Event \"?\"]
+[Site \"?\"]
+[Date \"2020.01.02\"]
+[Round \"?\"]
+[White \"Rob Chess\"]
+[Black \"A \\\"quoted\\\" name\"]
+[Result \"0-1\"]
+[Annotator \"Test\"]
+
+1. f3 e5 2. g4 Qh4# 0-1
+
+";
+    assert_eq!(g.to_pgn(), expected);
+}
+
+#[test]
+fn to_pgn_includes_setup_for_custom_start() {
+    let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+    let g = GameContext::from_fen(fen).unwrap();
+    let pgn = g.to_pgn();
+
+    assert!(pgn.contains("[SetUp \"1\"]\n"));
+    assert!(pgn.contains(&format!("[FEN \"{}\"]\n", fen)));
+    assert!(pgn.ends_with("\n*\n\n"));
+}
+
+#[test]
+fn to_pgn_wraps_long_movetext() {
+    let mut g = GameContext::new();
+    for _ in 0..10 {
+        play(&mut g, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+    }
+    let pgn = g.to_pgn();
+
+    let movetext = pgn.split("\n\n").nth(1).unwrap();
+    assert!(movetext.lines().count() > 1);
+    assert!(movetext.lines().all(|line| line.len() <= 79));
+}
+
+const ANNOTATED_GAME: &str = r#"[Event "Casual \"blitz\""]
+[Site "?"]
+[Date "2021.03.04"]
+[Round "1"]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+
+{Opening comment} 1. e4 e5 2. Nf3 $1 Nc6 {Defending} (2... d6 3. d4 (3. Bc4) 3... exd4) 3. Bb5!?
+; rest of line comment
+a6 4. Ba4 Nf6 5. O-O 1-0
+
+[Event "Second"]
+[Result "*"]
+
+1. d4 d5 *
+"#;
+
+#[test]
+fn parse_games_reads_tags_comments_nags_and_variations() {
+    let games = parse_games(ANNOTATED_GAME).unwrap();
+    assert_eq!(games.len(), 2);
+
+    let game = &games[0];
+    assert_eq!(game.tag("Event"), Some("Casual \"blitz\""));
+    assert_eq!(game.tag("White"), Some("Alice"));
+    assert_eq!(game.result, Some("1-0".to_owned()));
+    assert_eq!(game.comments, vec!["Opening comment".to_owned()]);
+
+    let sans: Vec<&str> = game.moves.iter().map(|m| m.san.as_str()).collect();
+    assert_eq!(
+        sans,
+        vec!["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4", "Nf6", "O-O"]
+    );
+    assert_eq!(game.moves[2].nags, vec![1]);
+    assert_eq!(game.moves[3].comments, vec!["Defending".to_owned()]);
+    assert_eq!(game.moves[4].nags, vec![5]);
+    assert_eq!(
+        game.moves[4].comments,
+        vec!["rest of line comment".to_owned()]
+    );
+
+    let variation = &game.moves[3].variations[0];
+    let variation_sans: Vec<&str> = variation.iter().map(|m| m.san.as_str()).collect();
+    assert_eq!(variation_sans, vec!["d6", "d4", "exd4"]);
+    assert_eq!(variation[1].variations[0][0].san, "Bc4");
+
+    assert_eq!(games[1].tag("Event"), Some("Second"));
+    assert_eq!(games[1].result, Some("*".to_owned()));
+    assert_eq!(games[1].moves.len(), 2);
+}
+
+#[test]
+fn from_pgn_replays_mainline() {
+    let g = GameContext::from_pgn(ANNOTATED_GAME).unwrap();
+
+    assert_eq!(g.chess_moves.len(), 9);
+    assert_eq!(g.tag("Black"), Some("Bob"));
+    assert_eq!(g.tag("Result"), Some("1-0"));
+    assert_eq!(
+        g.position.to_fen(),
+        "r1bqkb1r/1ppp1ppp/p1n2n2/4p3/B3P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 3 5"
+    );
+}
+
+#[test]
+fn from_pgn_uses_fen_tag() {
+    let pgn = "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 12\"]\n\n12... Kd7 13. e4 *";
+    let g = GameContext::from_pgn(pgn).unwrap();
+
+    assert_eq!(g.movetext(), "12... Kd7 13. e4");
+}
+
+#[test]
+fn from_pgn_reports_illegal_move_ply_and_token() {
+    let pgn = "1. e4 e5 2. Nf3 Nf6 3. Ke3 *";
+
+    assert_eq!(
+        GameContext::from_pgn(pgn).unwrap_err(),
+        PgnError::IllegalMove(5, "Ke3".to_owned())
+    );
+}
+
+#[test]
+fn from_pgn_reports_unparseable_move_ply_and_token() {
+    let pgn = "1. e4 e5 2. Zz9 *";
+
+    assert_eq!(
+        GameContext::from_pgn(pgn).unwrap_err(),
+        PgnError::UnparseableMove(3, "Zz9".to_owned())
+    );
+}
+
+#[test]
+fn parse_games_reports_syntax_errors() {
+    match parse_games("1. e4 (1. d4 d5") {
+        Err(PgnError::Syntax(1, _)) => (),
+        other => panic!("Expected a syntax error but got {:?}", other),
+    }
+    match parse_games("[Event \"Unterminated]\n\n1. e4 *") {
+        Err(PgnError::Syntax(_, _)) => (),
+        other => panic!("Expected a syntax error but got {:?}", other),
+    }
+}
+
+#[test]
+fn exported_pgn_can_be_imported() {
+    let mut g = GameContext::new();
+    play(
+        &mut g,
+        &["d4", "Nf6", "c4", "e6", "Nc3", "Bb4", "Qc2", "O-O"],
+    );
+
+    let imported = GameContext::from_pgn(&g.to_pgn()).unwrap();
+    assert_eq!(imported.position.to_fen(), g.position.to_fen());
+    assert_eq!(imported.movetext(), g.movetext());
+}
@@ -0,0 +1,400 @@
+use crate::game::chess_move::{ChessMove, ParseMoveError};
+use crate::game::game_context::GameContext;
+use crate::game::position::FenError;
+use std::error::Error;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+// PgnGame is a game as read from PGN, before its moves have been checked against the rules.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    // Comments appearing before the first move.
+    pub comments: Vec<String>,
+    pub moves: Vec<PgnMove>,
+    // The game termination marker, e.g. "1-0" or "*". Missing if the movetext did not end in one.
+    pub result: Option<String>,
+}
+
+// PgnMove is a move of movetext along with the annotations attached to it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PgnMove {
+    pub san: String,
+    // Numeric annotation glyphs. Suffix annotations such as ! and ?! are converted to their NAGs.
+    pub nags: Vec<u32>,
+    pub comments: Vec<String>,
+    // Alternatives to this move, each of which starts from the position before it.
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+// PgnError describes why PGN could not be read. Move errors carry the ply, counted from 1 at the
+// first move of the game, and the movetext token of the offending move.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PgnError {
+    Syntax(usize, String),
+    InvalidFen(FenError),
+    UnparseableMove(usize, String),
+    IllegalMove(usize, String),
+}
+
+impl Error for PgnError {}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::Syntax(line, message) => {
+                write!(f, "Syntax error on line {}: {}", line, message)
+            }
+            PgnError::InvalidFen(err) => write!(f, "Invalid FEN tag: {}", err),
+            PgnError::UnparseableMove(ply, token) => {
+                write!(f, "Could not understand move '{}' at ply {}", token, ply)
+            }
+            PgnError::IllegalMove(ply, token) => {
+                write!(f, "Illegal move '{}' at ply {}", token, ply)
+            }
+        }
+    }
+}
+
+impl PgnGame {
+    // Replays the mainline from the game's starting position, checking each move is legal.
+    pub fn replay(&self) -> Result<GameContext, PgnError> {
+        let mut g = match self.tag("FEN") {
+            Some(fen) => GameContext::from_fen(fen).map_err(PgnError::InvalidFen)?,
+            None => GameContext::new(),
+        };
+        for (name, value) in self.tags.iter() {
+            g.set_tag(name, value);
+        }
+
+        for (i, pgn_move) in self.moves.iter().enumerate() {
+            let ply = i + 1;
+            let chess_move =
+                ChessMove::from_san(&pgn_move.san, &g.position).map_err(|err| match err {
+                    ParseMoveError::IllegalMoveError => {
+                        PgnError::IllegalMove(ply, pgn_move.san.clone())
+                    }
+                    _ => PgnError::UnparseableMove(ply, pgn_move.san.clone()),
+                })?;
+            g.make_move(chess_move)
+                .map_err(|_| PgnError::IllegalMove(ply, pgn_move.san.clone()))?;
+        }
+
+        if let Some(result) = &self.result {
+            g.set_tag("Result", result);
+        }
+        Ok(g)
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl GameContext {
+    // Reads the first game of a PGN text and replays it.
+    pub fn from_pgn(pgn: &str) -> Result<GameContext, PgnError> {
+        match parse_games(pgn)?.first() {
+            Some(game) => game.replay(),
+            None => Err(PgnError::Syntax(1, "No game found".to_owned())),
+        }
+    }
+}
+
+// Parses every game in a PGN text. Moves are not checked against the rules until replayed.
+pub fn parse_games(pgn: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut parser = Parser {
+        lexer: Lexer::new(pgn),
+        peeked: None,
+    };
+    let mut games = Vec::<PgnGame>::new();
+    while parser.peek()?.is_some() {
+        games.push(parser.parse_game()?);
+    }
+    Ok(games)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    TagStart,
+    TagEnd,
+    VariationStart,
+    VariationEnd,
+    Period,
+    Asterisk,
+    Str(String),
+    Symbol(String),
+    Comment(String),
+    Nag(u32),
+}
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    at_line_start: bool,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(text: &'a str) -> Self {
+        Lexer {
+            chars: text.chars().peekable(),
+            line: 1,
+            at_line_start: true,
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        self.at_line_start = c == Some('\n');
+        if self.at_line_start {
+            self.line += 1;
+        }
+        c
+    }
+
+    fn syntax_error(&self, message: &str) -> PgnError {
+        PgnError::Syntax(self.line, message.to_owned())
+    }
+
+    // Returns the next token and the line it started on, or None at the end of the text.
+    fn next_token(&mut self) -> Result<Option<(Token, usize)>, PgnError> {
+        loop {
+            let c = match self.chars.peek() {
+                Some(c) => *c,
+                None => return Ok(None),
+            };
+            // A % in the first column escapes the rest of the line.
+            if c == '%' && self.at_line_start {
+                while !matches!(self.bump(), Some('\n') | None) {}
+                continue;
+            }
+            if c.is_whitespace() {
+                self.bump();
+                continue;
+            }
+
+            let line = self.line;
+            self.bump();
+            let token = match c {
+                '[' => Token::TagStart,
+                ']' => Token::TagEnd,
+                '(' => Token::VariationStart,
+                ')' => Token::VariationEnd,
+                '.' => Token::Period,
+                '*' => Token::Asterisk,
+                '"' => Token::Str(self.read_string()?),
+                '{' => Token::Comment(self.read_brace_comment()?),
+                ';' => {
+                    let mut comment = String::new();
+                    while let Some(c) = self.chars.peek() {
+                        if *c == '\n' {
+                            break;
+                        }
+                        comment.push(*c);
+                        self.bump();
+                    }
+                    Token::Comment(comment.trim().to_owned())
+                }
+                '$' => {
+                    let digits = self.read_while(|c| c.is_ascii_digit());
+                    let nag = digits
+                        .parse::<u32>()
+                        .map_err(|_| self.syntax_error("Expected digits after $"))?;
+                    Token::Nag(nag)
+                }
+                '!' | '?' => {
+                    let suffix = c.to_string() + &self.read_while(|c| c == '!' || c == '?');
+                    let nag = match suffix.as_str() {
+                        "!" => 1,
+                        "?" => 2,
+                        "!!" => 3,
+                        "??" => 4,
+                        "!?" => 5,
+                        "?!" => 6,
+                        _ => {
+                            return Err(self.syntax_error(&format!("Unknown annotation {}", suffix)))
+                        }
+                    };
+                    Token::Nag(nag)
+                }
+                c if c.is_ascii_alphanumeric() => {
+                    let rest =
+                        self.read_while(|c| c.is_ascii_alphanumeric() || "_+#=:-/".contains(c));
+                    Token::Symbol(c.to_string() + &rest)
+                }
+                c => return Err(self.syntax_error(&format!("Unexpected character '{}'", c))),
+            };
+            return Ok(Some((token, line)));
+        }
+    }
+
+    fn read_while<F: Fn(char) -> bool>(&mut self, accept: F) -> String {
+        let mut s = String::new();
+        while let Some(c) = self.chars.peek() {
+            if !accept(*c) {
+                break;
+            }
+            s.push(*c);
+            self.bump();
+        }
+        s
+    }
+
+    fn read_string(&mut self) -> Result<String, PgnError> {
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(s),
+                Some('\\') => match self.bump() {
+                    Some(c) => s.push(c),
+                    None => break,
+                },
+                Some(c) => s.push(c),
+                None => break,
+            }
+        }
+        Err(self.syntax_error("Unterminated string"))
+    }
+
+    fn read_brace_comment(&mut self) -> Result<String, PgnError> {
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                Some('}') => return Ok(s.trim().to_owned()),
+                Some(c) => s.push(c),
+                None => return Err(self.syntax_error("Unterminated comment")),
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    peeked: Option<Option<(Token, usize)>>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&mut self) -> Result<Option<&Token>, PgnError> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lexer.next_token()?);
+        }
+        Ok(self
+            .peeked
+            .as_ref()
+            .and_then(|t| t.as_ref().map(|(t, _)| t)))
+    }
+
+    fn next(&mut self) -> Result<Option<(Token, usize)>, PgnError> {
+        match self.peeked.take() {
+            Some(token) => Ok(token),
+            None => self.lexer.next_token(),
+        }
+    }
+
+    fn parse_game(&mut self) -> Result<PgnGame, PgnError> {
+        let mut game = PgnGame::default();
+
+        // Tag pair section.
+        while let Some(Token::TagStart) = self.peek()? {
+            self.next()?;
+            let name = match self.next()? {
+                Some((Token::Symbol(name), _)) => name,
+                _ => return Err(self.lexer.syntax_error("Expected a tag name")),
+            };
+            let value = match self.next()? {
+                Some((Token::Str(value), _)) => value,
+                _ => return Err(self.lexer.syntax_error("Expected a quoted tag value")),
+            };
+            match self.next()? {
+                Some((Token::TagEnd, _)) => (),
+                _ => return Err(self.lexer.syntax_error("Expected ] to close the tag")),
+            }
+            game.tags.push((name, value));
+        }
+
+        game.moves = self.parse_movetext(false, &mut game.comments, &mut game.result)?;
+        Ok(game)
+    }
+
+    // Parses moves until the end of the game, or until the end of the variation when in_variation.
+    fn parse_movetext(
+        &mut self,
+        in_variation: bool,
+        leading_comments: &mut Vec<String>,
+        result: &mut Option<String>,
+    ) -> Result<Vec<PgnMove>, PgnError> {
+        let mut moves = Vec::<PgnMove>::new();
+        loop {
+            // A new tag section means the previous game ended without a termination marker.
+            if !in_variation && self.peek()? == Some(&Token::TagStart) {
+                return Ok(moves);
+            }
+            let (token, line) = match self.next()? {
+                Some(token) => token,
+                None if in_variation => {
+                    return Err(self.lexer.syntax_error("Unterminated variation"));
+                }
+                None => return Ok(moves),
+            };
+            let syntax_error = |message: &str| PgnError::Syntax(line, message.to_owned());
+
+            match token {
+                Token::Period => (),
+                Token::Symbol(s) if s.chars().all(|c| c.is_ascii_digit()) => (),
+                Token::Symbol(ref s) if is_result(s) => {
+                    if in_variation {
+                        return Err(syntax_error("Game termination inside a variation"));
+                    }
+                    *result = Some(s.clone());
+                    return Ok(moves);
+                }
+                Token::Asterisk => {
+                    if in_variation {
+                        return Err(syntax_error("Game termination inside a variation"));
+                    }
+                    *result = Some("*".to_owned());
+                    return Ok(moves);
+                }
+                Token::Symbol(san) => moves.push(PgnMove {
+                    san,
+                    ..PgnMove::default()
+                }),
+                Token::Nag(nag) => match moves.last_mut() {
+                    Some(m) => m.nags.push(nag),
+                    None => return Err(syntax_error("Annotation before any move")),
+                },
+                Token::Comment(comment) => match moves.last_mut() {
+                    Some(m) => m.comments.push(comment),
+                    None => leading_comments.push(comment),
+                },
+                Token::VariationStart => {
+                    let mut variation_comments = Vec::<String>::new();
+                    let mut variation =
+                        self.parse_movetext(true, &mut variation_comments, &mut None)?;
+                    // Comments before a variation's first move are kept with that move.
+                    if let Some(first) = variation.first_mut() {
+                        variation_comments.append(&mut first.comments);
+                        first.comments = variation_comments;
+                    }
+                    match moves.last_mut() {
+                        Some(m) => m.variations.push(variation),
+                        None => return Err(syntax_error("Variation before any move")),
+                    }
+                }
+                Token::VariationEnd if in_variation => return Ok(moves),
+                Token::VariationEnd => return Err(syntax_error("Unmatched )")),
+                Token::TagStart | Token::TagEnd | Token::Str(_) => {
+                    return Err(syntax_error("Unexpected tag in movetext"))
+                }
+            }
+        }
+    }
+}
+
+fn is_result(s: &str) -> bool {
+    matches!(s, "1-0" | "0-1" | "1/2-1/2")
+}
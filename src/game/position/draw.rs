@@ -0,0 +1,42 @@
+use super::{Position, Square};
+use crate::game::color::Color;
+use crate::game::game_piece::Piece;
+
+impl Position {
+    // A double push only gives the right to capture en passant if a pawn stands ready to do so.
+    pub(super) fn can_capture_en_passant(&self, s: Square) -> bool {
+        let pawn_rank = match self.side_to_move {
+            Color::White => s.rank - 1,
+            Color::Black => s.rank + 1,
+        };
+        [s.file.wrapping_sub(1), s.file + 1]
+            .iter()
+            .filter(|&&f| f <= 7)
+            .any(|&f| {
+                self.board[pawn_rank][f]
+                    .is_some_and(|p| p.piece == Piece::Pawn && p.color == self.side_to_move)
+            })
+    }
+
+    // Whether neither side has the pieces to ever give checkmate: kings alone, a single minor
+    // piece, or bishops that all travel on squares of the same color.
+    pub fn has_insufficient_material(&self) -> bool {
+        let mut knights = 0;
+        let mut bishop_square_colors = [false; 2];
+        for (r, rank) in self.board.iter().enumerate() {
+            for (f, square) in rank.iter().enumerate() {
+                match square.map(|p| p.piece) {
+                    None | Some(Piece::King) => {}
+                    Some(Piece::Knight) => knights += 1,
+                    Some(Piece::Bishop) => bishop_square_colors[(r + f) % 2] = true,
+                    Some(_) => return false,
+                }
+            }
+        }
+        match knights {
+            0 => bishop_square_colors != [true; 2],
+            1 => bishop_square_colors == [false; 2],
+            _ => false,
+        }
+    }
+}
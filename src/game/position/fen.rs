@@ -0,0 +1,269 @@
+use super::{CastlingRights, Position, Square};
+use crate::game::color::Color;
+use crate::game::game_piece::{GamePiece, Piece};
+use std::error::Error;
+use std::fmt;
+
+// The FEN of the standard starting position.
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// FenError describes why a FEN string could not be parsed into a Position.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FenError {
+    // A FEN needs at least the placement, side to move, castling and en passant fields.
+    FieldCount(usize),
+    RankCount(usize),
+    // The rank (1-8) described the wrong number of squares.
+    RankLength(usize, usize),
+    // The rank (1-8) gave a run of empty squares other than as a single digit from 1 to 8.
+    InvalidEmptySquares(usize),
+    InvalidPiece(char),
+    KingCount(Color, usize),
+    // Pawns can never stand on the first or eighth rank.
+    PawnOnBackRank(Square),
+    InvalidSideToMove(String),
+    InvalidCastlingRights(String),
+    InvalidEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+    // The side that just moved has left its king in check, so the king could be captured.
+    OpponentInCheck(Color),
+}
+
+impl Error for FenError {}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::FieldCount(n) => write!(f, "Expected 4 to 6 FEN fields but found {}", n),
+            FenError::RankCount(n) => {
+                write!(f, "Expected 8 ranks in piece placement but found {}", n)
+            }
+            FenError::RankLength(rank, n) => {
+                write!(f, "Rank {} describes {} squares instead of 8", rank, n)
+            }
+            FenError::InvalidEmptySquares(rank) => {
+                write!(
+                    f,
+                    "Rank {} has a run of empty squares that isn't 1 to 8",
+                    rank
+                )
+            }
+            FenError::InvalidPiece(c) => write!(f, "'{}' is not a valid piece", c),
+            FenError::KingCount(color, n) => {
+                write!(f, "{:?} must have exactly one king but has {}", color, n)
+            }
+            FenError::PawnOnBackRank(s) => write!(f, "There is a pawn on {}", s),
+            FenError::InvalidSideToMove(s) => write!(f, "'{}' is not a valid side to move", s),
+            FenError::InvalidCastlingRights(s) => {
+                write!(f, "'{}' is not a valid castling availability", s)
+            }
+            FenError::InvalidEnPassant(s) => write!(f, "'{}' is not a valid en passant square", s),
+            FenError::InvalidHalfmoveClock(s) => write!(f, "'{}' is not a valid halfmove clock", s),
+            FenError::InvalidFullmoveNumber(s) => {
+                write!(f, "'{}' is not a valid fullmove number", s)
+            }
+            FenError::OpponentInCheck(color) => {
+                write!(f, "{:?} is in check but it is not their move", color)
+            }
+        }
+    }
+}
+
+impl Position {
+    // Parses a position from Forsyth-Edwards Notation. The halfmove clock and fullmove number may
+    // be omitted, as they are in EPD, in which case they default to 0 and 1.
+    pub fn from_fen(fen: &str) -> Result<Position, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 4 || fields.len() > 6 {
+            return Err(FenError::FieldCount(fields.len()));
+        }
+
+        let mut p = Position::new();
+        p.board = [[None; 8]; 8];
+
+        // Ranks are listed from the eighth down to the first.
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::RankCount(ranks.len()));
+        }
+        for (i, rank_str) in ranks.iter().enumerate() {
+            let r = 7 - i;
+            let mut f = 0;
+            let mut after_digit = false;
+            for c in rank_str.chars() {
+                if let Some(skip) = c.to_digit(10) {
+                    if !(1..=8).contains(&skip) || after_digit {
+                        return Err(FenError::InvalidEmptySquares(r + 1));
+                    }
+                    f += skip as usize;
+                    after_digit = true;
+                } else {
+                    after_digit = false;
+                    let piece = GamePiece::from_char(c).ok_or(FenError::InvalidPiece(c))?;
+                    if f < 8 {
+                        p.board[r][f] = Some(piece);
+                    }
+                    f += 1;
+                }
+            }
+            if f != 8 {
+                return Err(FenError::RankLength(r + 1, f));
+            }
+        }
+        for color in &[Color::White, Color::Black] {
+            let king = Some(GamePiece::new(Piece::King, *color));
+            let kings = p.board.iter().flatten().filter(|&&s| s == king).count();
+            if kings != 1 {
+                return Err(FenError::KingCount(*color, kings));
+            }
+        }
+        for &r in &[0, 7] {
+            for f in 0..8 {
+                if p.board[r][f].is_some_and(|gp| gp.piece == Piece::Pawn) {
+                    return Err(FenError::PawnOnBackRank(Square::new(f, r)));
+                }
+            }
+        }
+
+        p.side_to_move = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            s => return Err(FenError::InvalidSideToMove(s.to_owned())),
+        };
+        let opponent = p.side_to_move.opp_color();
+        if p.in_check(opponent) {
+            return Err(FenError::OpponentInCheck(opponent));
+        }
+
+        p.castling_rights = parse_castling_rights(fields[2])?;
+
+        p.en_passant = match fields[3] {
+            "-" => None,
+            s => {
+                let invalid = || FenError::InvalidEnPassant(s.to_owned());
+                let square = s.parse::<Square>().map_err(|_| invalid())?;
+                // The square skipped by the last double push lies behind the side to move's
+                // opponent. It and the square the pawn came from are empty, with the pawn in
+                // front of them.
+                let (expected_rank, forward) = match p.side_to_move {
+                    Color::White => (5, -1),
+                    Color::Black => (2, 1),
+                };
+                if square.rank != expected_rank {
+                    return Err(invalid());
+                }
+                let pawn_rank = (square.rank as isize + forward) as usize;
+                let from_rank = (square.rank as isize - forward) as usize;
+                let pawn = GamePiece::new(Piece::Pawn, p.side_to_move.opp_color());
+                if p.board[square.rank][square.file].is_some()
+                    || p.board[from_rank][square.file].is_some()
+                    || p.board[pawn_rank][square.file] != Some(pawn)
+                {
+                    return Err(invalid());
+                }
+                Some(square)
+            }
+        };
+
+        p.halfmove_clock = match fields.get(4) {
+            Some(s) => s
+                .parse::<u32>()
+                .map_err(|_| FenError::InvalidHalfmoveClock((*s).to_owned()))?,
+            None => 0,
+        };
+        p.fullmove_number = match fields.get(5) {
+            Some(s) => match s.parse::<u32>() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(FenError::InvalidFullmoveNumber((*s).to_owned())),
+            },
+            None => 1,
+        };
+
+        p.hash = p.compute_hash();
+        Ok(p)
+    }
+
+    // Returns the position in Forsyth-Edwards Notation.
+    pub fn to_fen(&self) -> String {
+        let mut placement = String::new();
+        for r in (0..8).rev() {
+            let mut empty = 0;
+            for f in 0..8 {
+                match self.board[r][f] {
+                    Some(p) => {
+                        if empty > 0 {
+                            placement += &empty.to_string();
+                            empty = 0;
+                        }
+                        placement.push(p.to_char());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                placement += &empty.to_string();
+            }
+            if r > 0 {
+                placement.push('/');
+            }
+        }
+
+        let side = match self.side_to_move {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+
+        let cr = &self.castling_rights;
+        let mut castling = String::new();
+        for (right, c) in &[
+            (cr.o_o_white, 'K'),
+            (cr.o_o_o_white, 'Q'),
+            (cr.o_o_black, 'k'),
+            (cr.o_o_o_black, 'q'),
+        ] {
+            if *right {
+                castling.push(*c);
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = self
+            .en_passant
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "-".to_owned());
+
+        format!(
+            "{} {} {} {} {} {}",
+            placement, side, castling, en_passant, self.halfmove_clock, self.fullmove_number
+        )
+    }
+}
+
+fn parse_castling_rights(castling: &str) -> Result<CastlingRights, FenError> {
+    let mut cr = CastlingRights::new();
+    if castling == "-" {
+        return Ok(cr);
+    }
+    let invalid = || FenError::InvalidCastlingRights(castling.to_owned());
+    if castling.is_empty() {
+        return Err(invalid());
+    }
+    for c in castling.chars() {
+        let right = match c {
+            'K' => &mut cr.o_o_white,
+            'Q' => &mut cr.o_o_o_white,
+            'k' => &mut cr.o_o_black,
+            'q' => &mut cr.o_o_o_black,
+            _ => return Err(invalid()),
+        };
+        // Each right may only be listed once.
+        if *right {
+            return Err(invalid());
+        }
+        *right = true;
+    }
+    Ok(cr)
+}
@@ -1,5 +1,11 @@
+mod draw;
+mod fen;
+mod perft;
 #[cfg(test)]
 mod position_tests;
+mod zobrist;
+
+pub use fen::{FenError, START_FEN};
 
 use super::chess_move::ChessMove;
 use super::color::Color;
@@ -7,9 +13,10 @@ use super::game_piece::{GamePiece, Piece};
 use std::fmt;
 use std::iter::Rev;
 use std::ops::Range;
+use std::str::FromStr;
 
 // Square represents a square in a chess position. Squares can have a piece placed on them.
-#[derive(Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Square {
     pub file: usize,
     pub rank: usize,
@@ -21,16 +28,66 @@ impl Square {
     }
 }
 
+impl FromStr for Square {
+    type Err = ();
+
+    // Parses a square from its name, e.g. e4.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(f @ 'a'..='h'), Some(r @ '1'..='8'), None) => Ok(Square::new(
+                f as usize - 'a' as usize,
+                r as usize - '1' as usize,
+            )),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file = (b'a' + self.file as u8) as char;
+        write!(f, "{}{}", file, self.rank + 1)
+    }
+}
+
 type Board = [[Option<GamePiece>; 8]; 8];
 
+// The pieces a pawn may promote to, strongest first.
+const PROMOTION_PIECES: [Piece; 4] = [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight];
+
+// LookResult is the result of looking along a line: the squares traversed and the piece collided with.
+type LookResult = (Vec<Square>, Option<GamePiece>);
+type LookFn = fn(&Position, usize, usize) -> LookResult;
+
 // Position represents a chess position representation.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Position {
     pub board: Board,
     pub castling_rights: CastlingRights,
+    // The square a pawn skipped over with a double push on the previous move, if any.
+    pub en_passant: Option<Square>,
+    pub side_to_move: Color,
+    // The number of moves since the last capture or pawn move, for the fifty-move rule.
+    pub halfmove_clock: u32,
+    // The number of the full move being played. It starts at 1 and increments after Black moves.
+    pub fullmove_number: u32,
+    // The Zobrist key of the position, identifying it for repetitions and the transposition table.
+    pub hash: u64,
+}
+
+// UndoState holds what make_move cannot recover from the move itself. It is handed back to
+// undo_move to restore the position.
+#[derive(Copy, Clone, Debug)]
+pub struct UndoState {
+    pub castling_rights_changes: CastlingRights,
+    pub en_passant: Option<Square>,
+    pub halfmove_clock: u32,
+    // Whether the move captured en passant, removing a pawn from beside its destination.
+    pub en_passant_capture: bool,
 }
 
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub struct CastlingRights {
     o_o_black: bool,
     o_o_o_black: bool,
@@ -40,12 +97,13 @@ pub struct CastlingRights {
 
 impl CastlingRights {
     pub fn new() -> Self {
-        CastlingRights {
-            o_o_black: false,
-            o_o_o_black: false,
-            o_o_white: false,
-            o_o_o_white: false,
-        }
+        Self::default()
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -60,6 +118,11 @@ impl Position {
                 o_o_o_black: true,
                 o_o_black: true,
             },
+            en_passant: None,
+            side_to_move: Color::White,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            hash: 0,
         };
 
         pos.reset();
@@ -105,24 +168,94 @@ impl Position {
         self.castling_rights.o_o_white = true;
         self.castling_rights.o_o_o_black = true;
         self.castling_rights.o_o_black = true;
+        self.en_passant = None;
+        self.side_to_move = Color::White;
+        self.halfmove_clock = 0;
+        self.fullmove_number = 1;
+        self.hash = self.compute_hash();
     }
 
-    pub fn make_move(&mut self, m: &ChessMove) -> Result<CastlingRights, ()> {
+    pub fn make_move(&mut self, m: &ChessMove) -> Result<UndoState, ()> {
         let or = m.o_rank;
         let of = m.o_file;
         let nr = m.n_rank;
         let nf = m.n_file;
 
         if of > 7 || or > 7 || nf > 7 || nr > 7 {
-            Err(())
+            return Err(());
+        }
+        #[cfg(feature = "verify-hash")]
+        let before = self.hash_snapshot();
+
+        // The key for castling rights, en passant and the side to move is swapped for that of
+        // the new state once the move has been made.
+        self.hash ^= self.state_hash();
+        let undo = UndoState {
+            castling_rights_changes: self.maybe_remove_castling(m),
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            en_passant_capture: self.is_en_passant(m),
+        };
+
+        // An en passant capture removes the pawn beside the origin square rather than one on
+        // the destination square.
+        if undo.en_passant_capture {
+            self.set_square(nf, or, None);
+        }
+        self.en_passant =
+            if m.moved_piece.piece == Piece::Pawn && (or as isize - nr as isize).abs() == 2 {
+                Some(Square::new(of, (or + nr) / 2))
+            } else {
+                None
+            };
+
+        // Make normal move, replacing a promoting pawn with its new piece.
+        let piece = match m.promo_piece {
+            Some(promo) => Some(GamePiece::new(promo, m.moved_piece.color)),
+            None => self.board[or][of],
+        };
+        self.set_square(of, or, None);
+        self.set_square(nf, nr, piece);
+
+        // Castling also relocates the rook.
+        if m.is_castle() {
+            let (rook_from, rook_to) = Self::castling_rook_files(nf);
+            self.set_square(rook_to, nr, self.board[nr][rook_from]);
+            self.set_square(rook_from, nr, None);
+        }
+
+        // Pass the move to the other side. Captures and pawn moves reset the fifty-move count.
+        if m.moved_piece.piece == Piece::Pawn || m.captured_piece.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if m.moved_piece.color == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = m.moved_piece.color.opp_color();
+        self.hash ^= self.state_hash();
+
+        #[cfg(feature = "verify-hash")]
+        self.verify_hash(before, "make_move");
+        Ok(undo)
+    }
+
+    // Returns whether the move is an en passant capture in this position, i.e. a pawn capturing
+    // onto the empty en passant square.
+    fn is_en_passant(&self, m: &ChessMove) -> bool {
+        m.moved_piece.piece == Piece::Pawn
+            && m.o_file != m.n_file
+            && self.en_passant == Some(Square::new(m.n_file, m.n_rank))
+            && self.board[m.n_rank][m.n_file].is_none()
+    }
+
+    // Returns the files the rook moves from and to when the king castles to king_file.
+    fn castling_rook_files(king_file: usize) -> (usize, usize) {
+        if king_file == 6 {
+            (7, 5)
         } else {
-            // TODO handle promotion
-            let castling_rights_changes = self.maybe_remove_castling(m);
-            // Make normal move
-            let piece = self.board[m.o_rank][m.o_file];
-            self.board[m.o_rank][m.o_file] = None;
-            self.board[m.n_rank][m.n_file] = piece;
-            Ok(castling_rights_changes)
+            (0, 3)
         }
     }
 
@@ -130,49 +263,42 @@ impl Position {
     // does not indicate it is now false, but instead that it was not modified.
     // This is useful for later undoing a move.
     pub fn maybe_remove_castling(&mut self, m: &ChessMove) -> CastlingRights {
-        // TODO isn't this weird that we can mutate self.castling_rights with a normal ol
-        // reference?
         let cr = &mut self.castling_rights;
         let mut changed = CastlingRights::new();
-        if let Some(p) = self.board[m.o_rank][m.o_file] {
-            if p.piece == Piece::King {
-                if !cr.o_o_black && p.color == Color::Black {
-                    cr.o_o_black = false;
-                    changed.o_o_black = true;
-                } else if !cr.o_o_white && p.color == Color::White {
-                    cr.o_o_white = false;
-                    changed.o_o_white = true;
-                }
-            }
-            if !cr.o_o_black && (m.o_rank == 7 && m.o_file == 7 || m.n_rank == 7 && m.n_file == 7) {
-                cr.o_o_black = false;
-                changed.o_o_black = true;
-            } else if !cr.o_o_white
-                && (m.o_rank == 0 && m.o_file == 7 || m.n_rank == 0 && m.n_file == 7)
-            {
-                cr.o_o_white = false;
-                changed.o_o_white = true;
-            } else if !cr.o_o_o_black
-                && (m.o_rank == 7 && m.o_file == 0 || m.n_rank == 7 && m.n_file == 0)
-            {
-                cr.o_o_o_black = false;
-                changed.o_o_o_black = true;
-            } else if !cr.o_o_o_white
-                && (m.o_rank == 0 && m.o_file == 0 || m.n_rank == 0 && m.n_file == 0)
-            {
-                cr.o_o_o_white = false;
-                changed.o_o_o_white = true;
-            }
+
+        // A move from or to a corner means the rook there has moved or been captured. A king move
+        // loses both rights, which is handled by treating it as moving both of its rooks.
+        let touches =
+            |f: usize, r: usize| m.o_rank == r && m.o_file == f || m.n_rank == r && m.n_file == f;
+        let king_moved =
+            |color: Color| m.moved_piece.piece == Piece::King && m.moved_piece.color == color;
+
+        if cr.o_o_white && (touches(7, 0) || king_moved(Color::White)) {
+            cr.o_o_white = false;
+            changed.o_o_white = true;
+        }
+        if cr.o_o_o_white && (touches(0, 0) || king_moved(Color::White)) {
+            cr.o_o_o_white = false;
+            changed.o_o_o_white = true;
+        }
+        if cr.o_o_black && (touches(7, 7) || king_moved(Color::Black)) {
+            cr.o_o_black = false;
+            changed.o_o_black = true;
+        }
+        if cr.o_o_o_black && (touches(0, 7) || king_moved(Color::Black)) {
+            cr.o_o_o_black = false;
+            changed.o_o_o_black = true;
         }
         changed
     }
 
-    pub fn undo_move(
-        &mut self,
-        chess_move: &ChessMove,
-        castling_rights_changes: CastlingRights,
-    ) -> Result<(), ()> {
+    pub fn undo_move(&mut self, chess_move: &ChessMove, undo: UndoState) -> Result<(), ()> {
+        #[cfg(feature = "verify-hash")]
+        let before = self.hash_snapshot();
+        self.hash ^= self.state_hash();
+
         // Undo castling rights changes
+        let castling_rights_changes = undo.castling_rights_changes;
         if castling_rights_changes.o_o_white {
             self.castling_rights.o_o_white = true;
         }
@@ -186,17 +312,68 @@ impl Position {
             self.castling_rights.o_o_o_black = true;
         }
 
-        // TODO Promotion
+        // Give the move back to the side which made it.
+        self.side_to_move = chess_move.moved_piece.color;
+        self.halfmove_clock = undo.halfmove_clock;
+        if chess_move.moved_piece.color == Color::Black {
+            self.fullmove_number -= 1;
+        }
 
-        // Undo move
-        self.board[chess_move.o_rank][chess_move.o_file] = Some(chess_move.moved_piece);
-        self.board[chess_move.n_rank][chess_move.n_file] = chess_move.captured_piece;
+        // Undo move. Restoring the moved piece also turns a promoted piece back into a pawn.
+        self.en_passant = undo.en_passant;
+        let (of, or) = (chess_move.o_file, chess_move.o_rank);
+        let (nf, nr) = (chess_move.n_file, chess_move.n_rank);
+        self.set_square(of, or, Some(chess_move.moved_piece));
+        if undo.en_passant_capture {
+            self.set_square(nf, nr, None);
+            self.set_square(nf, or, chess_move.captured_piece);
+        } else {
+            self.set_square(nf, nr, chess_move.captured_piece);
+        }
 
+        // Put a castled rook back in its corner.
+        if chess_move.is_castle() {
+            let (rook_from, rook_to) = Self::castling_rook_files(nf);
+            self.set_square(rook_from, nr, self.board[nr][rook_to]);
+            self.set_square(rook_to, nr, None);
+        }
+
+        self.hash ^= self.state_hash();
+        #[cfg(feature = "verify-hash")]
+        self.verify_hash(before, "undo_move");
         Ok(())
     }
 
-    // TODO combine check pruning with get_moves_at for mem/cpu efficiency boost.
-    pub fn get_moves(&mut self, color: Color) -> Vec<ChessMove> {
+    // Passes the move to the other side without moving anything. The search uses this to ask
+    // whether a position is so good that it would hold even if a move could be skipped.
+    pub fn make_null_move(&mut self) -> UndoState {
+        self.hash ^= self.state_hash();
+        let undo = UndoState {
+            castling_rights_changes: CastlingRights::new(),
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            en_passant_capture: false,
+        };
+        self.en_passant = None;
+        // Positions before a null move can't be repeated by the moves after it.
+        self.halfmove_clock = 0;
+        self.side_to_move = self.side_to_move.opp_color();
+        self.hash ^= self.state_hash();
+        undo
+    }
+
+    pub fn undo_null_move(&mut self, undo: UndoState) {
+        self.hash ^= self.state_hash();
+        self.side_to_move = self.side_to_move.opp_color();
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.hash ^= self.state_hash();
+    }
+
+    // Returns the strictly legal moves for the side to move. Pseudo-legal moves are generated per
+    // piece and then any move which would leave the mover's king in check is pruned.
+    pub fn get_moves(&mut self) -> Vec<ChessMove> {
+        let color = self.side_to_move;
         let mut moves = Vec::<ChessMove>::with_capacity(20);
 
         /* Only pieces can make moves in chess, so we iterate through the board and check for pieces.
@@ -212,16 +389,34 @@ impl Position {
         }
 
         // Prune moves which lead to checks
-        let mut valid_moves = Vec::<ChessMove>::with_capacity(20);
+        let mut valid_moves = Vec::<ChessMove>::with_capacity(moves.len());
         for m in moves.into_iter() {
-            //            if self.causes_check(&m, color) {
-            valid_moves.push(m);
-            //            }
+            if !self.causes_check(&m) {
+                valid_moves.push(m);
+            }
         }
 
         valid_moves
     }
 
+    // Returns whether a move is legal in this position. The move must be one the side to move can
+    // make from its origin square, and it must not leave the mover's king in check.
+    pub fn is_legal(&mut self, m: &ChessMove) -> bool {
+        if m.moved_piece.color != self.side_to_move {
+            return false;
+        }
+        if m.o_file > 7 || m.o_rank > 7 || m.n_file > 7 || m.n_rank > 7 {
+            return false;
+        }
+        match self.board[m.o_rank][m.o_file] {
+            Some(p) if p == m.moved_piece => (),
+            _ => return false,
+        }
+
+        let is_candidate = self.get_moves_at(m.o_file, m.o_rank).contains(m);
+        is_candidate && !self.causes_check(m)
+    }
+
     fn get_moves_at(&self, f: usize, r: usize) -> Vec<ChessMove> {
         let mut moves = Vec::<ChessMove>::with_capacity(20);
         let piece = self.board[r][f];
@@ -241,7 +436,6 @@ impl Position {
         moves
     }
 
-    // TODO teach about en passant.
     fn get_pawn_moves(&self, f: usize, r: usize, color: Color) -> Vec<ChessMove> {
         let mut moves = Vec::<ChessMove>::with_capacity(4);
 
@@ -250,7 +444,7 @@ impl Position {
             Color::Black => -1,
         };
 
-        // TODO remove and teach about promotion.
+        // A pawn can never stand on its last rank, as it promotes on arrival.
         if r == 7 && color == Color::White || r == 0 && color == Color::Black {
             return moves;
         }
@@ -259,7 +453,7 @@ impl Position {
         let r_up = (r as i32 + r_incr) as usize;
         let r_up_2 = (r as i32 + r_incr * 2) as usize;
         if r == 1 && color == Color::White || r == 6 && color == Color::Black {
-            if let None = self.board[r_up][f] {
+            if self.board[r_up][f].is_none() {
                 moves.push(ChessMove::new(
                     self.board[r][f].expect("Expected pawn in get_pawn_moves."),
                     f,
@@ -269,7 +463,7 @@ impl Position {
                     None,
                     self.board[r_up][f],
                 ));
-                if let None = self.board[r_up_2][f] {
+                if self.board[r_up_2][f].is_none() {
                     moves.push(ChessMove::new(
                         self.board[r][f].expect("Expected pawn in get_pawn_moves."),
                         f,
@@ -281,22 +475,22 @@ impl Position {
                     ));
                 }
             }
-        } else if r > 1 && color == Color::White || r < 6 && color == Color::Black {
-            if let None = self.board[r_up][f] {
-                moves.push(ChessMove::new(
-                    self.board[r][f].expect("Expected pawn in get_pawn_moves."),
-                    f,
-                    r,
-                    f,
-                    r_up,
-                    None,
-                    self.board[r_up][f],
-                ))
-            }
+        } else if (r > 1 && color == Color::White || r < 6 && color == Color::Black)
+            && self.board[r_up][f].is_none()
+        {
+            moves.push(ChessMove::new(
+                self.board[r][f].expect("Expected pawn in get_pawn_moves."),
+                f,
+                r,
+                f,
+                r_up,
+                None,
+                self.board[r_up][f],
+            ))
         }
 
         // Possible captures
-        if f as i32 - 1 >= 0 {
+        if f >= 1 {
             let f_left = (f as i32 - 1) as usize;
             let capture_piece = self.board[r_up][f_left];
             match capture_piece {
@@ -329,6 +523,41 @@ impl Position {
                 _ => (),
             }
         }
+
+        // En passant captures the pawn which just passed the capturing pawn's diagonal. That pawn
+        // must still be beside the capturing pawn, with the square it passed over left empty.
+        if let Some(ep) = self.en_passant {
+            let passed_pawn = Some(GamePiece::new(Piece::Pawn, color.opp_color()));
+            if ep.rank == r_up
+                && (ep.file as isize - f as isize).abs() == 1
+                && self.board[ep.rank][ep.file].is_none()
+                && self.board[r][ep.file] == passed_pawn
+            {
+                moves.push(ChessMove::new(
+                    self.board[r][f].expect("Expected pawn in get_pawn_moves."),
+                    f,
+                    r,
+                    ep.file,
+                    ep.rank,
+                    None,
+                    self.board[r][ep.file],
+                ));
+            }
+        }
+
+        // Moves onto the last rank are expanded into a promotion to each possible piece.
+        if r_up == 7 || r_up == 0 {
+            return moves
+                .into_iter()
+                .flat_map(|m| {
+                    PROMOTION_PIECES.iter().map(move |&promo| {
+                        let mut promotion = m;
+                        promotion.promo_piece = Some(promo);
+                        promotion
+                    })
+                })
+                .collect();
+        }
         moves
     }
 
@@ -479,34 +708,65 @@ impl Position {
             ));
         }
 
-        // TODO Castle
+        moves.append(&mut self.get_castling_moves(f, r, color));
         moves
     }
 
-    // TODO self should probably not be mutable in this function. Fix later.
-    fn causes_check(&mut self, m: &ChessMove, color: Color) -> bool {
-        let old_piece = self.board[m.o_rank][m.o_file];
-        let captured_piece = self.board[m.n_rank][m.n_file];
-        let mut to_return = false;
+    // Returns the castling moves available to the king on f, r. Castling requires the right to
+    // castle, an empty path between king and rook, and that the king is not in check and does not
+    // pass through an attacked square.
+    fn get_castling_moves(&self, f: usize, r: usize, color: Color) -> Vec<ChessMove> {
+        let mut moves = Vec::<ChessMove>::with_capacity(2);
+        let home_rank = match color {
+            Color::White => 0,
+            Color::Black => 7,
+        };
+        if f != 4 || r != home_rank {
+            return moves;
+        }
 
-        self.make_move(&m)
-            .expect("Could not make expected move in causes_check().");
-        let king_square = self.get_king_square(color);
-        if self.in_check(king_square.file, king_square.rank, color) {
-            to_return = true;
+        let cr = &self.castling_rights;
+        let (o_o, o_o_o) = match color {
+            Color::White => (cr.o_o_white, cr.o_o_o_white),
+            Color::Black => (cr.o_o_black, cr.o_o_o_black),
+        };
+        let king = self.board[r][f].expect("Expected king in get_castling_moves.");
+        let rook = Some(GamePiece::new(Piece::Rook, color));
+        let empty = |files: &[usize]| files.iter().all(|&file| self.board[r][file].is_none());
+        let safe = |files: &[usize]| {
+            files
+                .iter()
+                .all(|&file| !self.is_square_attacked(&Square::new(file, r), color.opp_color()))
+        };
+
+        if o_o && self.board[r][7] == rook && empty(&[5, 6]) && safe(&[4, 5, 6]) {
+            moves.push(ChessMove::new(king, f, r, 6, r, None, None));
         }
+        if o_o_o && self.board[r][0] == rook && empty(&[1, 2, 3]) && safe(&[4, 3, 2]) {
+            moves.push(ChessMove::new(king, f, r, 2, r, None, None));
+        }
+        moves
+    }
+
+    // Returns whether making the move would leave the mover's king in check. The position is
+    // restored before returning.
+    fn causes_check(&mut self, m: &ChessMove) -> bool {
+        let undo = self
+            .make_move(m)
+            .expect("Could not make expected move in causes_check().");
+        let to_return = self.in_check(m.moved_piece.color);
 
         // Roll back the move.
-        self.board[m.o_rank][m.o_file] = old_piece;
-        self.board[m.n_rank][m.n_file] = captured_piece;
+        self.undo_move(m, undo)
+            .expect("Could not roll back move in causes_check().");
         to_return
     }
 
-    fn get_king_square(&self, color: Color) -> Square {
+    pub fn get_king_square(&self, color: Color) -> Square {
         for r in 0..self.board.len() {
             for f in 0..self.board[r].len() {
                 if let Some(p) = self.board[r][f] {
-                    if p.color == color {
+                    if p.piece == Piece::King && p.color == color {
                         return Square::new(f, r);
                     }
                 }
@@ -515,53 +775,110 @@ impl Position {
         panic!("No king found in position");
     }
 
-    // TODO Expand to king and pawns.
-    fn in_check(&self, f: usize, r: usize, color: Color) -> bool {
-        let check_straight = |func: fn(&Self, usize, usize) -> (Vec<Square>, Option<GamePiece>)| {
-            if let (_, Some(p)) = func(self, f, r) {
-                match p.piece {
-                    Piece::Rook | Piece::Queen if p.color == color => return true,
-                    _ => return false,
+    // Returns whether color's king is currently in check.
+    pub fn in_check(&self, color: Color) -> bool {
+        let king_square = self.get_king_square(color);
+        self.is_square_attacked(&king_square, color.opp_color())
+    }
+
+    // Returns whether any piece of by_color attacks the square. The square itself may be occupied
+    // by a piece of either color.
+    pub fn is_square_attacked(&self, s: &Square, by_color: Color) -> bool {
+        let mut attacked = false;
+        self.visit_attackers(s, |_, p| {
+            attacked = p.color == by_color;
+            attacked
+        });
+        attacked
+    }
+
+    // Returns the squares of all pieces, of either color, which attack the square.
+    pub fn attackers_of(&self, s: &Square) -> Vec<Square> {
+        let mut attackers = Vec::<Square>::with_capacity(4);
+        self.visit_attackers(s, |attacker, _| {
+            attackers.push(attacker);
+            false
+        });
+        attackers
+    }
+
+    // Calls visit with each piece attacking the square and the square it stands on. Visiting stops
+    // early once visit returns true.
+    fn visit_attackers<F>(&self, s: &Square, mut visit: F)
+    where
+        F: FnMut(Square, GamePiece) -> bool,
+    {
+        let (f, r) = (s.file, s.rank);
+
+        // Sliding pieces, kings and pawns are found by looking outward from the square. Kings and
+        // pawns only attack when the collision happens on the first square looked at.
+        let rays: [(LookFn, bool); 8] = [
+            (Self::look_up, false),
+            (Self::look_right, false),
+            (Self::look_down, false),
+            (Self::look_left, false),
+            (Self::look_up_right, true),
+            (Self::look_down_right, true),
+            (Self::look_down_left, true),
+            (Self::look_up_left, true),
+        ];
+        for (look, diagonal) in rays.iter() {
+            let (squares, piece) = look(self, f, r);
+            let p = match piece {
+                Some(p) => p,
+                None => continue,
+            };
+            let attacker = squares
+                .last()
+                .expect("A collision implies a traversed square.");
+            let adjacent = squares.len() == 1;
+            let attacks = match p.piece {
+                Piece::Queen => true,
+                Piece::Rook => !diagonal,
+                Piece::Bishop => *diagonal,
+                Piece::King => adjacent,
+                // Pawns capture forward, so a white pawn attacks from the rank below.
+                Piece::Pawn => {
+                    *diagonal
+                        && adjacent
+                        && match p.color {
+                            Color::White => attacker.rank < r,
+                            Color::Black => attacker.rank > r,
+                        }
                 }
+                Piece::Knight => false,
+            };
+            if attacks && visit(Square::new(attacker.file, attacker.rank), p) {
+                return;
             }
-            false
-        };
-        let check_diagonal = |func: fn(&Self, usize, usize) -> (Vec<Square>, Option<GamePiece>)| {
-            if let (_, Some(p)) = func(self, f, r) {
-                match p.piece {
-                    Piece::Bishop | Piece::Queen if p.color == color => return true,
-                    _ => return false,
+        }
+
+        for attacker in self.look_l(f, r) {
+            if let Some(p) = self.board[attacker.rank][attacker.file] {
+                if p.piece == Piece::Knight && visit(attacker, p) {
+                    return;
                 }
             }
-            false
-        };
-        check_straight(Self::look_up)
-            || check_straight(Self::look_right)
-            || check_straight(Self::look_down)
-            || check_straight(Self::look_left)
-            || check_diagonal(Self::look_up_right)
-            || check_diagonal(Self::look_down_right)
-            || check_diagonal(Self::look_down_left)
-            || check_diagonal(Self::look_up_right)
+        }
     }
 
     /* lookUp and other look functions look in a direction on the board from a starting square.
      * When another piece is encountered, the function returns with the squares traversed and
      * the collision piece. */
-    fn look_up(&self, f: usize, r: usize) -> (Vec<Square>, Option<GamePiece>) {
+    fn look_up(&self, f: usize, r: usize) -> LookResult {
         let mut squares = Vec::<Square>::new();
         let mut piece = None;
         for i in BoardRange::new(r, 1, 7) {
             squares.push(Square::new(f, i));
-            if self.board[i as usize][f].is_some() {
-                piece = self.board[i as usize][f];
+            if self.board[i][f].is_some() {
+                piece = self.board[i][f];
                 break;
             }
         }
         (squares, piece)
     }
 
-    fn look_up_right(&self, f: usize, r: usize) -> (Vec<Square>, Option<GamePiece>) {
+    fn look_up_right(&self, f: usize, r: usize) -> LookResult {
         let mut squares = Vec::<Square>::with_capacity(20);
         let mut piece = None;
         for i in BoardRange::new(r, 1, 7) {
@@ -578,20 +895,20 @@ impl Position {
         (squares, piece)
     }
 
-    fn look_right(&self, f: usize, r: usize) -> (Vec<Square>, Option<GamePiece>) {
+    fn look_right(&self, f: usize, r: usize) -> LookResult {
         let mut squares = Vec::<Square>::new();
         let mut piece = None;
         for i in BoardRange::new(f, 1, 7) {
             squares.push(Square::new(i, r));
-            if self.board[r][i as usize].is_some() {
-                piece = self.board[r][i as usize];
+            if self.board[r][i].is_some() {
+                piece = self.board[r][i];
                 break;
             }
         }
         (squares, piece)
     }
 
-    fn look_down_right(&self, f: usize, r: usize) -> (Vec<Square>, Option<GamePiece>) {
+    fn look_down_right(&self, f: usize, r: usize) -> LookResult {
         let mut squares = Vec::<Square>::with_capacity(20);
         let mut piece = None;
         for i in BoardRange::new(r, -1, 0) {
@@ -608,20 +925,20 @@ impl Position {
         (squares, piece)
     }
 
-    fn look_down(&self, f: usize, r: usize) -> (Vec<Square>, Option<GamePiece>) {
+    fn look_down(&self, f: usize, r: usize) -> LookResult {
         let mut squares = Vec::<Square>::new();
         let mut piece = None;
         for i in BoardRange::new(r, -1, 0) {
             squares.push(Square::new(f, i));
-            if self.board[i as usize][f].is_some() {
-                piece = self.board[i as usize][f];
+            if self.board[i][f].is_some() {
+                piece = self.board[i][f];
                 break;
             }
         }
         (squares, piece)
     }
 
-    fn look_down_left(&self, f: usize, r: usize) -> (Vec<Square>, Option<GamePiece>) {
+    fn look_down_left(&self, f: usize, r: usize) -> LookResult {
         let mut squares = Vec::<Square>::with_capacity(20);
         let mut piece = None;
         for i in BoardRange::new(r, -1, 0) {
@@ -638,20 +955,20 @@ impl Position {
         (squares, piece)
     }
 
-    fn look_left(&self, f: usize, r: usize) -> (Vec<Square>, Option<GamePiece>) {
+    fn look_left(&self, f: usize, r: usize) -> LookResult {
         let mut squares = Vec::<Square>::new();
         let mut piece = None;
         for i in BoardRange::new(f, -1, 0) {
-            squares.push(Square::new(i as usize, r));
-            if self.board[r][i as usize].is_some() {
-                piece = self.board[r][i as usize];
+            squares.push(Square::new(i, r));
+            if self.board[r][i].is_some() {
+                piece = self.board[r][i];
                 break;
             }
         }
         (squares, piece)
     }
 
-    fn look_up_left(&self, f: usize, r: usize) -> (Vec<Square>, Option<GamePiece>) {
+    fn look_up_left(&self, f: usize, r: usize) -> LookResult {
         let mut squares = Vec::<Square>::with_capacity(20);
         let mut piece = None;
         for i in BoardRange::new(r, 1, 7) {
@@ -675,15 +992,15 @@ impl Position {
         if f + 2 < 8 && r + 1 < 8 {
             squares.push(Square::new(f + 2, r + 1));
         }
-        if f + 2 < 8 && r as i32 - 1 >= 0 {
+        if f + 2 < 8 && r >= 1 {
             squares.push(Square::new(f + 2, r - 1));
         }
 
         // Left L moves
-        if f as i32 - 2 >= 0 && r + 1 < 8 {
+        if f >= 2 && r + 1 < 8 {
             squares.push(Square::new(f - 2, r + 1));
         }
-        if f as i32 - 2 >= 0 && r as i32 - 1 >= 0 {
+        if f >= 2 && r >= 1 {
             squares.push(Square::new(f - 2, r - 1));
         }
 
@@ -691,15 +1008,15 @@ impl Position {
         if f + 1 < 8 && r + 2 < 8 {
             squares.push(Square::new(f + 1, r + 2));
         }
-        if f as i32 - 1 >= 0 && r + 2 < 8 {
+        if f >= 1 && r + 2 < 8 {
             squares.push(Square::new(f - 1, r + 2));
         }
 
         // Backward L moves
-        if f + 1 < 8 && r as i32 - 2 >= 0 {
+        if f + 1 < 8 && r >= 2 {
             squares.push(Square::new(f + 1, r - 2));
         }
-        if f as i32 - 1 >= 0 && r as i32 - 2 >= 0 {
+        if f >= 1 && r >= 2 {
             squares.push(Square::new(f - 1, r - 2));
         }
         squares
@@ -711,11 +1028,13 @@ impl Position {
         color: Color,
         f: usize,
         r: usize,
-        look_result: (Vec<Square>, Option<GamePiece>),
-    ) -> () {
+        look_result: LookResult,
+    ) {
         let (squares, piece) = look_result;
         for (i, s) in squares.iter().enumerate() {
-            if i == squares.len() - 1 && piece.is_some() && piece.unwrap().color == color {
+            // Only the last square traversed can hold a piece.
+            let is_last = i == squares.len() - 1;
+            if is_last && piece.is_some() && piece.unwrap().color == color {
                 continue;
             }
             moves.push(ChessMove::new(
@@ -725,42 +1044,45 @@ impl Position {
                 s.file,
                 s.rank,
                 None,
-                piece,
+                if is_last { piece } else { None },
             ));
         }
     }
 
     // Returns if a piece can move to a specific square.
     fn can_move_to_square(&self, f: isize, r: isize, color: Color) -> bool {
-        if f < 0 || f > 7 || r < 0 || r > 7 {
+        if !(0..=7).contains(&f) || !(0..=7).contains(&r) {
             return false;
         }
 
         match self.board[r as usize][f as usize] {
-            Some(p) => {
-                if p.color == color {
-                    false
-                } else {
-                    true
-                }
-            }
+            Some(p) => p.color != color,
             None => true,
         }
     }
 
-    pub fn sum_material(&self, color: Color) -> f64 {
+    // Whether color has any pieces besides its king and pawns. Without them, zugzwang is common.
+    pub fn has_non_pawn_material(&self, color: Color) -> bool {
+        self.board
+            .iter()
+            .flatten()
+            .flatten()
+            .any(|p| p.color == color && p.piece != Piece::Pawn && p.piece != Piece::King)
+    }
+
+    pub fn sum_material(&self, color: Color) -> i32 {
         let mut sum = 0;
         for r in BoardRange::new(0, 0, 7) {
             for f in BoardRange::new(0, 0, 7) {
                 let piece = self.board[r][f];
                 if let Some(p) = piece {
                     if p.color == color {
-                        sum += p.piece.value();
+                        sum += p.value();
                     }
                 }
             }
         }
-        sum as f64
+        sum
     }
 }
 
@@ -787,7 +1109,7 @@ impl BoardRange {
         let start = start as isize + start_modifier;
         let end = end as isize;
         // BoardRanges that begin off the board should be empty.
-        if start > 7 || start < 0 {
+        if !(0..=7).contains(&start) {
             return BoardRange::Forward(0..0);
         }
         if start >= end {
@@ -815,6 +1137,6 @@ impl fmt::Display for Position {
             }
         }
         board_print += "     a   b   c   d   e   f   g   h\n";
-        return write!(f, "{}", board_print);
+        write!(f, "{}", board_print)
     }
 }
@@ -0,0 +1,46 @@
+use super::Position;
+use crate::game::chess_move::ChessMove;
+
+impl Position {
+    // Counts the positions reachable in exactly depth moves. Comparing the counts with known
+    // values is the standard way of checking move generation.
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.get_moves();
+        // The moves are legal, so the positions after them needn't be visited to be counted.
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        moves
+            .iter()
+            .map(|m| {
+                let undo = self
+                    .make_move(m)
+                    .unwrap_or_else(|_| panic!("Failed to make move {}", m));
+                let nodes = self.perft(depth - 1);
+                self.undo_move(m, undo).expect("Failed to roll back move.");
+                nodes
+            })
+            .sum()
+    }
+
+    // Splits the perft count by the first move, to find where move generation goes wrong.
+    pub fn divide(&mut self, depth: u32) -> Vec<(ChessMove, u64)> {
+        if depth == 0 {
+            return vec![];
+        }
+        self.get_moves()
+            .into_iter()
+            .map(|m| {
+                let undo = self
+                    .make_move(&m)
+                    .unwrap_or_else(|_| panic!("Failed to make move {}", m));
+                let nodes = self.perft(depth - 1);
+                self.undo_move(&m, undo).expect("Failed to roll back move.");
+                (m, nodes)
+            })
+            .collect()
+    }
+}
@@ -1,22 +1,25 @@
+#![allow(clippy::bool_assert_comparison)]
+
+use crate::game::chess_move::ChessMove;
 use crate::game::color::Color;
 use crate::game::game_piece::GamePiece;
 use crate::game::game_piece::Piece;
-use crate::game::position::{BoardRange, Position, Square};
+use crate::game::position::{BoardRange, FenError, Position, Square, START_FEN};
 
 #[test]
 fn reset_resets_castling_rights() {
     let mut p = Position::new();
-    p.o_o_o_white = false;
-    p.o_o_white = false;
-    p.o_o_o_black = false;
-    p.o_o_black = false;
+    p.castling_rights.o_o_o_white = false;
+    p.castling_rights.o_o_white = false;
+    p.castling_rights.o_o_o_black = false;
+    p.castling_rights.o_o_black = false;
 
     p.reset();
 
-    assert_eq!(p.o_o_o_white, true);
-    assert_eq!(p.o_o_white, true);
-    assert_eq!(p.o_o_o_black, true);
-    assert_eq!(p.o_o_black, true);
+    assert_eq!(p.castling_rights.o_o_o_white, true);
+    assert_eq!(p.castling_rights.o_o_white, true);
+    assert_eq!(p.castling_rights.o_o_o_black, true);
+    assert_eq!(p.castling_rights.o_o_black, true);
 }
 
 #[test]
@@ -411,3 +414,735 @@ fn board_range_start_oob_empty() {
         BoardRange::Backward(_) => panic!("Board range is unexpectedly backward."),
     }
 }
+
+fn empty_position() -> Position {
+    let mut p = Position::new();
+    p.board = [[None; 8]; 8];
+    p
+}
+
+#[test]
+fn get_moves_start_position_count() {
+    let mut p = Position::new();
+
+    assert_eq!(p.get_moves().len(), 20);
+    p.side_to_move = Color::Black;
+    assert_eq!(p.get_moves().len(), 20);
+}
+
+#[test]
+fn get_moves_excludes_pinned_piece_moves() {
+    let mut p = empty_position();
+    p.board[0][4] = Some(GamePiece::new(Piece::King, Color::White));
+    p.board[1][4] = Some(GamePiece::new(Piece::Knight, Color::White));
+    p.board[7][4] = Some(GamePiece::new(Piece::Rook, Color::Black));
+    p.board[7][0] = Some(GamePiece::new(Piece::King, Color::Black));
+
+    let moves = p.get_moves();
+    assert!(moves.iter().all(|m| m.moved_piece.piece == Piece::King));
+}
+
+#[test]
+fn get_moves_excludes_discovered_checks() {
+    let mut p = empty_position();
+    p.board[0][0] = Some(GamePiece::new(Piece::King, Color::White));
+    p.board[1][1] = Some(GamePiece::new(Piece::Bishop, Color::White));
+    p.board[7][7] = Some(GamePiece::new(Piece::Queen, Color::Black));
+    p.board[7][0] = Some(GamePiece::new(Piece::King, Color::Black));
+
+    let moves = p.get_moves();
+    let bishop_moves: Vec<&ChessMove> = moves
+        .iter()
+        .filter(|m| m.moved_piece.piece == Piece::Bishop)
+        .collect();
+    assert_eq!(bishop_moves.len(), 6);
+    assert!(bishop_moves.iter().all(|m| m.n_file == m.n_rank));
+}
+
+#[test]
+fn get_moves_excludes_king_moves_into_attacked_squares() {
+    let mut p = empty_position();
+    p.board[0][4] = Some(GamePiece::new(Piece::King, Color::White));
+    p.board[7][3] = Some(GamePiece::new(Piece::Rook, Color::Black));
+    p.board[7][5] = Some(GamePiece::new(Piece::Rook, Color::Black));
+    p.board[7][0] = Some(GamePiece::new(Piece::King, Color::Black));
+
+    let moves = p.get_moves();
+    assert_eq!(moves.len(), 1);
+    assert_eq!((moves[0].n_file, moves[0].n_rank), (4, 1));
+}
+
+#[test]
+fn is_legal_rejects_moves_leaving_king_in_check() {
+    let mut p = empty_position();
+    p.board[0][4] = Some(GamePiece::new(Piece::King, Color::White));
+    p.board[1][4] = Some(GamePiece::new(Piece::Rook, Color::White));
+    p.board[7][4] = Some(GamePiece::new(Piece::Queen, Color::Black));
+    p.board[7][0] = Some(GamePiece::new(Piece::King, Color::Black));
+
+    let rook = GamePiece::new(Piece::Rook, Color::White);
+    let sideways = ChessMove::new(rook, 4, 1, 0, 1, None, None);
+    let forward = ChessMove::new(rook, 4, 1, 4, 5, None, None);
+    let queen = GamePiece::new(Piece::Queen, Color::Black);
+    let capture = ChessMove::new(rook, 4, 1, 4, 7, None, Some(queen));
+    let teleport = ChessMove::new(rook, 4, 1, 5, 2, None, None);
+
+    assert_eq!(p.is_legal(&sideways), false);
+    assert_eq!(p.is_legal(&forward), true);
+    assert_eq!(p.is_legal(&capture), true);
+    assert_eq!(p.is_legal(&teleport), false);
+}
+
+#[test]
+fn is_square_attacked_detects_every_piece_type() {
+    let mut p = empty_position();
+    let target = Square::new(3, 3);
+
+    p.board[5][4] = Some(GamePiece::new(Piece::Knight, Color::Black));
+    assert_eq!(p.is_square_attacked(&target, Color::Black), true);
+    assert_eq!(p.is_square_attacked(&target, Color::White), false);
+
+    p = empty_position();
+    p.board[4][4] = Some(GamePiece::new(Piece::King, Color::Black));
+    assert_eq!(p.is_square_attacked(&target, Color::Black), true);
+
+    p = empty_position();
+    p.board[5][5] = Some(GamePiece::new(Piece::King, Color::Black));
+    assert_eq!(p.is_square_attacked(&target, Color::Black), false);
+
+    p = empty_position();
+    p.board[0][0] = Some(GamePiece::new(Piece::Queen, Color::Black));
+    assert_eq!(p.is_square_attacked(&target, Color::Black), true);
+
+    p = empty_position();
+    p.board[6][0] = Some(GamePiece::new(Piece::Bishop, Color::Black));
+    assert_eq!(p.is_square_attacked(&target, Color::Black), true);
+
+    p = empty_position();
+    p.board[3][7] = Some(GamePiece::new(Piece::Rook, Color::Black));
+    assert_eq!(p.is_square_attacked(&target, Color::Black), true);
+
+    p = empty_position();
+    p.board[3][7] = Some(GamePiece::new(Piece::Bishop, Color::Black));
+    assert_eq!(p.is_square_attacked(&target, Color::Black), false);
+}
+
+#[test]
+fn is_square_attacked_respects_pawn_direction() {
+    let mut p = empty_position();
+    let target = Square::new(3, 3);
+    p.board[2][2] = Some(GamePiece::new(Piece::Pawn, Color::White));
+    p.board[4][4] = Some(GamePiece::new(Piece::Pawn, Color::Black));
+    assert_eq!(p.is_square_attacked(&target, Color::White), true);
+    assert_eq!(p.is_square_attacked(&target, Color::Black), true);
+
+    p = empty_position();
+    p.board[4][2] = Some(GamePiece::new(Piece::Pawn, Color::White));
+    p.board[2][4] = Some(GamePiece::new(Piece::Pawn, Color::Black));
+    p.board[2][3] = Some(GamePiece::new(Piece::Pawn, Color::White));
+    assert_eq!(p.is_square_attacked(&target, Color::White), false);
+    assert_eq!(p.is_square_attacked(&target, Color::Black), false);
+}
+
+#[test]
+fn is_square_attacked_is_blocked_by_pieces() {
+    let mut p = empty_position();
+    let target = Square::new(0, 0);
+    p.board[7][7] = Some(GamePiece::new(Piece::Bishop, Color::Black));
+    p.board[4][4] = Some(GamePiece::new(Piece::Pawn, Color::White));
+    assert_eq!(p.is_square_attacked(&target, Color::Black), false);
+}
+
+#[test]
+fn attackers_of_returns_attackers_of_both_colors() {
+    let p = Position::new();
+
+    // f3 is covered by the e2 and g2 pawns and the g1 knight.
+    let attackers = p.attackers_of(&Square::new(5, 2));
+    assert_eq!(attackers.len(), 3);
+    assert!(attackers.contains(&Square::new(4, 1)));
+    assert!(attackers.contains(&Square::new(6, 1)));
+    assert!(attackers.contains(&Square::new(6, 0)));
+
+    let mut p = empty_position();
+    p.board[0][0] = Some(GamePiece::new(Piece::Rook, Color::White));
+    p.board[7][7] = Some(GamePiece::new(Piece::Queen, Color::Black));
+    let attackers = p.attackers_of(&Square::new(0, 7));
+    assert_eq!(attackers, vec![Square::new(7, 7), Square::new(0, 0)]);
+}
+
+#[test]
+fn in_check_detects_knight_check() {
+    let mut p = empty_position();
+    p.board[0][4] = Some(GamePiece::new(Piece::King, Color::White));
+    p.board[2][5] = Some(GamePiece::new(Piece::Knight, Color::Black));
+    p.board[7][4] = Some(GamePiece::new(Piece::King, Color::Black));
+
+    assert_eq!(p.in_check(Color::White), true);
+    assert_eq!(p.in_check(Color::Black), false);
+}
+
+fn castling_position() -> Position {
+    let mut p = empty_position();
+    p.board[0][4] = Some(GamePiece::new(Piece::King, Color::White));
+    p.board[0][0] = Some(GamePiece::new(Piece::Rook, Color::White));
+    p.board[0][7] = Some(GamePiece::new(Piece::Rook, Color::White));
+    p.board[7][4] = Some(GamePiece::new(Piece::King, Color::Black));
+    p.board[7][0] = Some(GamePiece::new(Piece::Rook, Color::Black));
+    p.board[7][7] = Some(GamePiece::new(Piece::Rook, Color::Black));
+    p
+}
+
+fn castles(moves: &[ChessMove]) -> Vec<usize> {
+    moves
+        .iter()
+        .filter(|m| m.is_castle())
+        .map(|m| m.n_file)
+        .collect()
+}
+
+#[test]
+fn get_moves_generates_castling() {
+    let mut p = castling_position();
+
+    assert_eq!(castles(&p.get_moves()), vec![6, 2]);
+    p.side_to_move = Color::Black;
+    assert_eq!(castles(&p.get_moves()), vec![6, 2]);
+    p.side_to_move = Color::White;
+
+    p.castling_rights.o_o_white = false;
+    assert_eq!(castles(&p.get_moves()), vec![2]);
+}
+
+#[test]
+fn get_moves_excludes_castling_through_check() {
+    let mut p = castling_position();
+    p.board[5][5] = Some(GamePiece::new(Piece::Rook, Color::Black));
+    assert_eq!(castles(&p.get_moves()), vec![2]);
+
+    // The queenside rook may pass over an attacked b1.
+    p.board[5][1] = Some(GamePiece::new(Piece::Rook, Color::Black));
+    assert_eq!(castles(&p.get_moves()), vec![2]);
+
+    p.board[5][4] = Some(GamePiece::new(Piece::Rook, Color::Black));
+    assert_eq!(castles(&p.get_moves()), Vec::<usize>::new());
+}
+
+#[test]
+fn get_moves_excludes_castling_through_pieces() {
+    let mut p = castling_position();
+    p.board[0][1] = Some(GamePiece::new(Piece::Knight, Color::White));
+    p.board[0][6] = Some(GamePiece::new(Piece::Knight, Color::Black));
+
+    assert_eq!(castles(&p.get_moves()), Vec::<usize>::new());
+}
+
+#[test]
+fn make_move_and_undo_move_relocate_castling_rook() {
+    let mut p = castling_position();
+    let king = GamePiece::new(Piece::King, Color::Black);
+    let rook = GamePiece::new(Piece::Rook, Color::Black);
+    let o_o_o = ChessMove::new(king, 4, 7, 2, 7, None, None);
+
+    let changes = p.make_move(&o_o_o).unwrap();
+    assert_eq!(p.board[7][2], Some(king));
+    assert_eq!(p.board[7][3], Some(rook));
+    assert_eq!(p.board[7][0], None);
+    assert_eq!(p.board[7][4], None);
+    assert_eq!(p.castling_rights.o_o_black, false);
+    assert_eq!(p.castling_rights.o_o_o_black, false);
+    assert_eq!(p.castling_rights.o_o_white, true);
+
+    p.undo_move(&o_o_o, changes).unwrap();
+    assert_eq!(p.board[7][4], Some(king));
+    assert_eq!(p.board[7][0], Some(rook));
+    assert_eq!(p.board[7][2], None);
+    assert_eq!(p.board[7][3], None);
+    assert_eq!(p.castling_rights.o_o_black, true);
+    assert_eq!(p.castling_rights.o_o_o_black, true);
+}
+
+#[test]
+fn capturing_a_rook_removes_castling_rights() {
+    let mut p = castling_position();
+    let rook = GamePiece::new(Piece::Rook, Color::White);
+    let black_rook = GamePiece::new(Piece::Rook, Color::Black);
+    let capture = ChessMove::new(rook, 7, 0, 7, 7, None, Some(black_rook));
+
+    let changes = p.make_move(&capture).unwrap();
+    assert_eq!(p.castling_rights.o_o_white, false);
+    assert_eq!(p.castling_rights.o_o_black, false);
+    assert_eq!(p.castling_rights.o_o_o_white, true);
+    assert_eq!(p.castling_rights.o_o_o_black, true);
+
+    p.undo_move(&capture, changes).unwrap();
+    assert_eq!(p.castling_rights.o_o_white, true);
+    assert_eq!(p.castling_rights.o_o_black, true);
+}
+
+#[test]
+fn from_algebraic_parses_castling() {
+    let mut p = castling_position();
+
+    let king_move = ChessMove::from_algebraic("e1g1", &p).unwrap();
+    let o_o = ChessMove::from_algebraic("O-O", &p).unwrap();
+    assert!(king_move.is_castle());
+    assert_eq!(king_move, o_o);
+
+    p.side_to_move = Color::Black;
+    let o_o_o = ChessMove::from_algebraic("0-0-0", &p).unwrap();
+    assert_eq!((o_o_o.o_file, o_o_o.n_file, o_o_o.n_rank), (4, 2, 7));
+}
+
+#[test]
+fn make_move_records_en_passant_square() {
+    let mut p = Position::new();
+    let pawn = GamePiece::new(Piece::Pawn, Color::White);
+    let double_push = ChessMove::new(pawn, 4, 1, 4, 3, None, None);
+    let single_push = ChessMove::new(pawn, 3, 1, 3, 2, None, None);
+
+    let undo = p.make_move(&double_push).unwrap();
+    assert_eq!(p.en_passant, Some(Square::new(4, 2)));
+    p.undo_move(&double_push, undo).unwrap();
+    assert_eq!(p.en_passant, None);
+
+    p.make_move(&double_push).unwrap();
+    p.make_move(&single_push).unwrap();
+    assert_eq!(p.en_passant, None);
+}
+
+#[test]
+fn en_passant_capture_is_generated_made_and_undone() {
+    let mut p = empty_position();
+    let white_pawn = GamePiece::new(Piece::Pawn, Color::White);
+    let black_pawn = GamePiece::new(Piece::Pawn, Color::Black);
+    p.board[0][4] = Some(GamePiece::new(Piece::King, Color::White));
+    p.board[7][4] = Some(GamePiece::new(Piece::King, Color::Black));
+    p.board[4][4] = Some(white_pawn);
+    p.board[6][3] = Some(black_pawn);
+
+    let double_push = ChessMove::new(black_pawn, 3, 6, 3, 4, None, None);
+    p.make_move(&double_push).unwrap();
+
+    let moves = p.get_moves();
+    let capture = *moves
+        .iter()
+        .find(|m| m.moved_piece.piece == Piece::Pawn && m.n_file == 3)
+        .expect("Expected an en passant capture.");
+    assert_eq!((capture.n_file, capture.n_rank), (3, 5));
+    assert_eq!(capture.captured_piece, Some(black_pawn));
+
+    let undo = p.make_move(&capture).unwrap();
+    assert_eq!(p.board[5][3], Some(white_pawn));
+    assert_eq!(p.board[4][3], None);
+    assert_eq!(p.board[4][4], None);
+
+    p.undo_move(&capture, undo).unwrap();
+    assert_eq!(p.board[4][4], Some(white_pawn));
+    assert_eq!(p.board[4][3], Some(black_pawn));
+    assert_eq!(p.board[5][3], None);
+    assert_eq!(p.en_passant, Some(Square::new(3, 5)));
+}
+
+#[test]
+fn en_passant_needs_a_pawn_that_passed_an_empty_square() {
+    // A position can't be reached with a piece on the en passant square, so it is set directly.
+    let mut p = Position::from_fen("4k3/8/4q3/3P4/8/8/8/4K3 w - - 0 1").unwrap();
+    p.en_passant = Some(Square::new(4, 5));
+    p.hash = p.compute_hash();
+    let before = p.to_fen();
+    let captures: Vec<ChessMove> = p
+        .get_moves()
+        .into_iter()
+        .filter(|m| m.to_string() == "d5e6")
+        .collect();
+    // Only the ordinary capture of the queen is generated.
+    assert_eq!(captures.len(), 1);
+    let capture = captures[0];
+    assert_eq!(
+        capture.captured_piece,
+        Some(GamePiece::new(Piece::Queen, Color::Black))
+    );
+    let undo = p.make_move(&capture).unwrap();
+    p.undo_move(&capture, undo).unwrap();
+    assert_eq!(p.to_fen(), before);
+    assert_eq!(
+        p.board[5][4],
+        Some(GamePiece::new(Piece::Queen, Color::Black))
+    );
+
+    // A pawn standing on the en passant square is captured normally, and only once.
+    let mut p = Position::from_fen("4k3/8/4p3/3P4/8/8/8/4K3 w - - 0 1").unwrap();
+    p.en_passant = Some(Square::new(4, 5));
+    let captures = p
+        .get_moves()
+        .into_iter()
+        .filter(|m| m.to_string() == "d5e6");
+    assert_eq!(captures.count(), 1);
+
+    // Nor is a piece other than a pawn taken en passant.
+    let mut p = Position::from_fen("4k3/8/8/3Pn3/8/8/8/4K3 w - - 0 1").unwrap();
+    p.en_passant = Some(Square::new(4, 5));
+    assert!(p.get_moves().iter().all(|m| m.to_string() != "d5e6"));
+}
+
+#[test]
+fn en_passant_capture_exposing_king_is_illegal() {
+    let mut p = empty_position();
+    let white_pawn = GamePiece::new(Piece::Pawn, Color::White);
+    let black_pawn = GamePiece::new(Piece::Pawn, Color::Black);
+    p.board[4][0] = Some(GamePiece::new(Piece::King, Color::White));
+    p.board[4][7] = Some(GamePiece::new(Piece::Rook, Color::Black));
+    p.board[7][4] = Some(GamePiece::new(Piece::King, Color::Black));
+    p.board[4][4] = Some(white_pawn);
+    p.board[4][3] = Some(black_pawn);
+    p.en_passant = Some(Square::new(3, 5));
+
+    let capture = ChessMove::from_algebraic("e5d6", &p).unwrap();
+    assert_eq!(capture.captured_piece, Some(black_pawn));
+    assert_eq!(p.is_legal(&capture), false);
+}
+
+#[test]
+fn get_moves_generates_promotions() {
+    let mut p = empty_position();
+    p.board[0][4] = Some(GamePiece::new(Piece::King, Color::White));
+    p.board[7][0] = Some(GamePiece::new(Piece::King, Color::Black));
+    p.board[6][6] = Some(GamePiece::new(Piece::Pawn, Color::White));
+    p.board[7][7] = Some(GamePiece::new(Piece::Rook, Color::Black));
+
+    let promotions: Vec<ChessMove> = p
+        .get_moves()
+        .into_iter()
+        .filter(|m| m.moved_piece.piece == Piece::Pawn)
+        .collect();
+    assert_eq!(promotions.len(), 8);
+    assert!(promotions.iter().all(|m| m.promo_piece.is_some()));
+
+    let capture_promotions = promotions.iter().filter(|m| m.captured_piece.is_some());
+    assert_eq!(capture_promotions.count(), 4);
+    for promo in &[Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight] {
+        assert!(promotions.iter().any(|m| m.promo_piece == Some(*promo)));
+    }
+}
+
+#[test]
+fn make_move_and_undo_move_handle_promotion() {
+    let mut p = empty_position();
+    let pawn = GamePiece::new(Piece::Pawn, Color::Black);
+    let rook = GamePiece::new(Piece::Rook, Color::White);
+    p.board[0][4] = Some(GamePiece::new(Piece::King, Color::White));
+    p.board[7][4] = Some(GamePiece::new(Piece::King, Color::Black));
+    p.board[1][1] = Some(pawn);
+    p.board[0][0] = Some(rook);
+
+    let promotion = ChessMove::new(pawn, 1, 1, 0, 0, Some(Piece::Knight), Some(rook));
+    let undo = p.make_move(&promotion).unwrap();
+    assert_eq!(
+        p.board[0][0],
+        Some(GamePiece::new(Piece::Knight, Color::Black))
+    );
+    assert_eq!(p.board[1][1], None);
+    assert_eq!(p.castling_rights.o_o_o_white, false);
+
+    p.undo_move(&promotion, undo).unwrap();
+    assert_eq!(p.board[0][0], Some(rook));
+    assert_eq!(p.board[1][1], Some(pawn));
+    assert_eq!(p.castling_rights.o_o_o_white, true);
+}
+
+#[test]
+fn from_algebraic_parses_promotion() {
+    let mut p = empty_position();
+    p.board[0][4] = Some(GamePiece::new(Piece::King, Color::White));
+    p.board[7][0] = Some(GamePiece::new(Piece::King, Color::Black));
+    p.board[6][4] = Some(GamePiece::new(Piece::Pawn, Color::White));
+
+    let promotion = ChessMove::from_algebraic("e7e8q", &p).unwrap();
+    assert_eq!(promotion.promo_piece, Some(Piece::Queen));
+    assert_eq!(promotion.to_string(), "e7e8q");
+    assert_eq!(p.is_legal(&promotion), true);
+
+    let no_promotion = ChessMove::from_algebraic("e7e8", &p).unwrap();
+    assert_eq!(p.is_legal(&no_promotion), false);
+}
+
+#[test]
+fn make_move_and_undo_move_update_side_and_clocks() {
+    let mut p = Position::new();
+    let knight = GamePiece::new(Piece::Knight, Color::White);
+    let pawn = GamePiece::new(Piece::Pawn, Color::Black);
+    let knight_move = ChessMove::new(knight, 6, 0, 5, 2, None, None);
+    let pawn_move = ChessMove::new(pawn, 4, 6, 4, 4, None, None);
+
+    let knight_undo = p.make_move(&knight_move).unwrap();
+    assert_eq!(p.side_to_move, Color::Black);
+    assert_eq!(p.halfmove_clock, 1);
+    assert_eq!(p.fullmove_number, 1);
+
+    let pawn_undo = p.make_move(&pawn_move).unwrap();
+    assert_eq!(p.side_to_move, Color::White);
+    assert_eq!(p.halfmove_clock, 0);
+    assert_eq!(p.fullmove_number, 2);
+
+    p.undo_move(&pawn_move, pawn_undo).unwrap();
+    assert_eq!(p.side_to_move, Color::Black);
+    assert_eq!(p.halfmove_clock, 1);
+    assert_eq!(p.fullmove_number, 1);
+
+    p.undo_move(&knight_move, knight_undo).unwrap();
+    assert_eq!(p.side_to_move, Color::White);
+    assert_eq!(p.halfmove_clock, 0);
+    assert_eq!(p.fullmove_number, 1);
+}
+
+#[test]
+fn is_legal_rejects_moves_out_of_turn() {
+    let mut p = Position::new();
+    let pawn = GamePiece::new(Piece::Pawn, Color::Black);
+    let pawn_move = ChessMove::new(pawn, 4, 6, 4, 4, None, None);
+
+    assert_eq!(p.is_legal(&pawn_move), false);
+    p.side_to_move = Color::Black;
+    assert_eq!(p.is_legal(&pawn_move), true);
+}
+
+#[test]
+fn from_fen_parses_start_position() {
+    let p = Position::from_fen(START_FEN).unwrap();
+    let start = Position::new();
+
+    assert_eq!(p.board, start.board);
+    assert_eq!(p.castling_rights, start.castling_rights);
+    assert_eq!(p.side_to_move, Color::White);
+    assert_eq!(p.en_passant, None);
+    assert_eq!(p.halfmove_clock, 0);
+    assert_eq!(p.fullmove_number, 1);
+    assert_eq!(start.to_fen(), START_FEN);
+}
+
+#[test]
+fn fen_round_trips() {
+    let fens = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 12 40",
+        "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 3 17",
+    ];
+    for fen in fens.iter() {
+        assert_eq!(Position::from_fen(fen).unwrap().to_fen(), *fen);
+    }
+}
+
+#[test]
+fn from_fen_sets_game_state() {
+    let p =
+        Position::from_fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBN1 b Qk - 5 42").unwrap();
+
+    assert_eq!(p.side_to_move, Color::Black);
+    assert_eq!(p.castling_rights.o_o_white, false);
+    assert_eq!(p.castling_rights.o_o_o_white, true);
+    assert_eq!(p.castling_rights.o_o_black, true);
+    assert_eq!(p.castling_rights.o_o_o_black, false);
+    assert_eq!(p.halfmove_clock, 5);
+    assert_eq!(p.fullmove_number, 42);
+    assert_eq!(
+        p.board[4][3],
+        Some(GamePiece::new(Piece::Pawn, Color::Black))
+    );
+    assert_eq!(p.board[0][7], None);
+}
+
+#[test]
+fn from_fen_accepts_missing_clocks() {
+    let p = Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - -").unwrap();
+
+    assert_eq!(p.halfmove_clock, 0);
+    assert_eq!(p.fullmove_number, 1);
+}
+
+#[test]
+fn from_fen_reports_malformed_input() {
+    let cases = [
+        ("8/8/8/8 w - -", FenError::RankCount(4)),
+        ("4k3/8/8/8/8/8/8/4K3", FenError::FieldCount(1)),
+        ("4k3/8/8/8/8/8/8/4K4 w - - 0 1", FenError::RankLength(1, 9)),
+        ("4k3/8/8/8/8/8/8/4X3 w - - 0 1", FenError::InvalidPiece('X')),
+        (
+            "4k3/8/8/8/8/8/8/K70 w - - 0 1",
+            FenError::InvalidEmptySquares(1),
+        ),
+        (
+            "4k3/8/9/8/8/8/8/4K3 w - - 0 1",
+            FenError::InvalidEmptySquares(6),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/K34 w - - 0 1",
+            FenError::InvalidEmptySquares(1),
+        ),
+        (
+            "8/8/8/8/8/8/8/4K3 w - - 0 1",
+            FenError::KingCount(Color::Black, 0),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+            FenError::InvalidSideToMove("x".to_owned()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w KK - 0 1",
+            FenError::InvalidCastlingRights("KK".to_owned()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - e3 0 1",
+            FenError::InvalidEnPassant("e3".to_owned()),
+        ),
+        // The en passant square must be empty, behind the pawn that passed it, with the square
+        // that pawn came from empty too.
+        (
+            "4k3/8/4q3/3P4/8/8/8/4K3 w - e6 0 1",
+            FenError::InvalidEnPassant("e6".to_owned()),
+        ),
+        (
+            "4k3/8/8/3Pn3/8/8/8/4K3 w - e6 0 1",
+            FenError::InvalidEnPassant("e6".to_owned()),
+        ),
+        (
+            "4k3/4n3/8/3Pp3/8/8/8/4K3 w - e6 0 1",
+            FenError::InvalidEnPassant("e6".to_owned()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - x 1",
+            FenError::InvalidHalfmoveClock("x".to_owned()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 0",
+            FenError::InvalidFullmoveNumber("0".to_owned()),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K2P w - - 0 1",
+            FenError::PawnOnBackRank(Square::new(7, 0)),
+        ),
+        (
+            "3pk3/8/8/8/8/8/8/4K3 b - - 0 1",
+            FenError::PawnOnBackRank(Square::new(3, 7)),
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4R1K1 w - - 0 1",
+            FenError::OpponentInCheck(Color::Black),
+        ),
+    ];
+    for (fen, err) in cases.iter() {
+        assert_eq!(Position::from_fen(fen).unwrap_err(), *err);
+    }
+}
+
+#[test]
+fn insufficient_material() {
+    let drawn = [
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/1N2K3 w - - 0 1",
+        // Bishops on squares of one color, c1 and f8 both being dark.
+        "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+    ];
+    for fen in drawn.iter() {
+        assert_eq!(
+            Position::from_fen(fen).unwrap().has_insufficient_material(),
+            true
+        );
+    }
+
+    let playable = [
+        "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1",
+        // Bishops on squares of both colors, c1 being dark and c8 light.
+        "2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+    ];
+    for fen in playable.iter() {
+        assert_eq!(
+            Position::from_fen(fen).unwrap().has_insufficient_material(),
+            false
+        );
+    }
+}
+
+#[test]
+fn hash_ignores_en_passant_that_cannot_be_taken() {
+    let with_ep = Position::from_fen("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1").unwrap();
+    let without_ep = Position::from_fen("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1").unwrap();
+    assert_eq!(with_ep.hash, without_ep.hash);
+
+    let with_ep = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+    let without_ep = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").unwrap();
+    assert_ne!(with_ep.hash, without_ep.hash);
+}
+
+#[test]
+fn hash_is_kept_up_to_date() {
+    // Castling, en passant, promotion and the loss of castling rights all change the key.
+    let mut p = Position::from_fen("r3k2r/1P4p1/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+    let start = p.hash;
+    for m in p.get_moves() {
+        let undo = p.make_move(&m).unwrap();
+        assert_eq!(p.hash, p.compute_hash(), "after {}", m);
+        for reply in p.get_moves() {
+            let reply_undo = p.make_move(&reply).unwrap();
+            assert_eq!(p.hash, p.compute_hash(), "after {} {}", m, reply);
+            p.undo_move(&reply, reply_undo).unwrap();
+        }
+        p.undo_move(&m, undo).unwrap();
+        assert_eq!(p.hash, start, "after undoing {}", m);
+    }
+}
+
+#[test]
+fn hash_identifies_positions() {
+    let p = Position::new();
+    assert_eq!(p.hash, Position::from_fen(START_FEN).unwrap().hash);
+
+    // The same pieces with different rights or a different side to move are different positions.
+    let others = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+    ];
+    for fen in others.iter() {
+        assert_ne!(Position::from_fen(fen).unwrap().hash, p.hash);
+    }
+
+    // Transpositions reach the same key, whatever the clocks.
+    let mut a = Position::new();
+    let mut b = Position::new();
+    for m in &["g1f3", "g8f6", "b1c3"] {
+        let chess_move = ChessMove::from_algebraic(m, &a).unwrap();
+        a.make_move(&chess_move).unwrap();
+    }
+    for m in &["b1c3", "g8f6", "g1f3"] {
+        let chess_move = ChessMove::from_algebraic(m, &b).unwrap();
+        b.make_move(&chess_move).unwrap();
+    }
+    assert_eq!(a.hash, b.hash);
+}
+
+#[test]
+fn null_move_passes_the_turn() {
+    let mut p = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+    let start = p.hash;
+    let undo = p.make_null_move();
+    assert_eq!(p.side_to_move, Color::White);
+    assert_eq!(p.en_passant, None);
+    assert_eq!(p.hash, p.compute_hash());
+    p.undo_null_move(undo);
+    assert_eq!(p.side_to_move, Color::Black);
+    assert_eq!(p.en_passant, Some(Square::new(4, 2)));
+    assert_eq!(p.hash, start);
+}
+
+#[test]
+fn has_non_pawn_material_ignores_kings_and_pawns() {
+    let p = Position::from_fen("4k3/pppp4/8/8/8/8/4PPPP/2B1K3 w - - 0 1").unwrap();
+    assert_eq!(p.has_non_pawn_material(Color::White), true);
+    assert_eq!(p.has_non_pawn_material(Color::Black), false);
+}
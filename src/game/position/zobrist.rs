@@ -0,0 +1,112 @@
+use super::Position;
+use crate::game::color::Color;
+use crate::game::game_piece::{GamePiece, Piece};
+
+// A Zobrist key is the XOR of a random number for each feature of a position: a piece on a square,
+// each castling right, the en passant file and the side to move. Making a move only changes a few
+// features, so the key can be updated by XORing their numbers in and out.
+const PIECE_KEYS: usize = 0;
+const CASTLING_KEYS: usize = PIECE_KEYS + 12 * 64;
+const EN_PASSANT_KEYS: usize = CASTLING_KEYS + 4;
+const SIDE_KEY: usize = EN_PASSANT_KEYS + 8;
+
+static KEYS: [u64; SIDE_KEY + 1] = random_keys();
+
+// Fills the key table from a fixed seed with splitmix64, so keys are the same from run to run.
+const fn random_keys() -> [u64; SIDE_KEY + 1] {
+    let mut keys = [0; SIDE_KEY + 1];
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut i = 0;
+    while i < keys.len() {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+fn piece_key(gp: GamePiece, file: usize, rank: usize) -> u64 {
+    let piece = match gp.piece {
+        Piece::Pawn => 0,
+        Piece::Rook => 1,
+        Piece::Knight => 2,
+        Piece::Bishop => 3,
+        Piece::Queen => 4,
+        Piece::King => 5,
+    };
+    let color = match gp.color {
+        Color::White => 0,
+        Color::Black => 6,
+    };
+    KEYS[PIECE_KEYS + (color + piece) * 64 + rank * 8 + file]
+}
+
+impl Position {
+    // Computes the Zobrist key of the position from scratch. make_move and undo_move keep hash
+    // up to date without needing this.
+    pub fn compute_hash(&self) -> u64 {
+        let mut hash = self.state_hash();
+        for (r, rank) in self.board.iter().enumerate() {
+            for (f, square) in rank.iter().enumerate() {
+                if let Some(gp) = square {
+                    hash ^= piece_key(*gp, f, r);
+                }
+            }
+        }
+        hash
+    }
+
+    // The part of the key for everything but the pieces. An en passant square only counts if
+    // the capture can be made, so positions that are the same for the repetition rules share a
+    // key.
+    pub(super) fn state_hash(&self) -> u64 {
+        let cr = &self.castling_rights;
+        let rights = [cr.o_o_white, cr.o_o_o_white, cr.o_o_black, cr.o_o_o_black];
+        let mut hash = 0;
+        for (i, &right) in rights.iter().enumerate() {
+            if right {
+                hash ^= KEYS[CASTLING_KEYS + i];
+            }
+        }
+        if let Some(s) = self.en_passant.filter(|&s| self.can_capture_en_passant(s)) {
+            hash ^= KEYS[EN_PASSANT_KEYS + s.file];
+        }
+        if self.side_to_move == Color::Black {
+            hash ^= KEYS[SIDE_KEY];
+        }
+        hash
+    }
+
+    // Places a piece on, or clears, a square, keeping hash up to date.
+    pub(super) fn set_square(&mut self, file: usize, rank: usize, piece: Option<GamePiece>) {
+        if let Some(old) = self.board[rank][file] {
+            self.hash ^= piece_key(old, file, rank);
+        }
+        if let Some(new) = piece {
+            self.hash ^= piece_key(new, file, rank);
+        }
+        self.board[rank][file] = piece;
+    }
+
+    // With the verify-hash feature, make_move and undo_move check their updates to hash against
+    // keys computed from scratch. The change to the key is compared rather than the key itself,
+    // as code that edits board directly, like tests setting up positions, leaves hash stale.
+    #[cfg(feature = "verify-hash")]
+    pub(super) fn hash_snapshot(&self) -> (u64, u64) {
+        (self.hash, self.compute_hash())
+    }
+
+    #[cfg(feature = "verify-hash")]
+    pub(super) fn verify_hash(&self, before: (u64, u64), operation: &str) {
+        assert_eq!(
+            self.hash ^ before.0,
+            self.compute_hash() ^ before.1,
+            "{} updated the Zobrist key incorrectly in {}",
+            operation,
+            self.to_fen()
+        );
+    }
+}
@@ -0,0 +1,295 @@
+#[cfg(test)]
+mod uci_tests;
+
+use super::chess_move::ChessMove;
+use super::engine;
+use super::engine::limits::SearchLimits;
+use super::engine::options::{SearchOptions, MAX_THREADS};
+use super::engine::score::Score;
+use super::engine::transposition::{TranspositionTable, DEFAULT_HASH_MB, MAX_HASH_MB};
+use super::game_context::GameContext;
+use super::position::{FenError, START_FEN};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// The names search_option knows the search's techniques by.
+const SEARCH_OPTIONS: [&str; 5] = [
+    "NullMove",
+    "LateMoveReductions",
+    "ReverseFutility",
+    "Futility",
+    "CheckExtensions",
+];
+
+#[derive(Debug, PartialEq)]
+pub enum PositionError {
+    Syntax(String),
+    InvalidFen(FenError),
+    IllegalMove(String),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionError::Syntax(command) => write!(f, "malformed position command '{}'", command),
+            PositionError::InvalidFen(err) => write!(f, "invalid FEN: {}", err),
+            PositionError::IllegalMove(m) => write!(f, "illegal move '{}'", m),
+        }
+    }
+}
+
+impl Error for PositionError {}
+
+struct Uci {
+    game: GameContext,
+    // Shared with the search thread. Resizing replaces it, leaving any search with the old one.
+    tt: Arc<TranspositionTable>,
+    // The stop flag and thread of a search in progress.
+    search: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+    options: SearchOptions,
+}
+
+pub fn start_uci_session() {
+    let mut uci = Uci {
+        game: GameContext::new(),
+        tt: Arc::new(TranspositionTable::default()),
+        search: None,
+        options: SearchOptions::default(),
+    };
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if !uci.handle(&line) {
+            break;
+        }
+    }
+    uci.stop_search();
+}
+
+impl Uci {
+    // Responds to a single command from the GUI. Returns false when the session should end.
+    fn handle(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+        let command = match tokens.next() {
+            Some(command) => command,
+            None => return true,
+        };
+        let args: Vec<&str> = tokens.collect();
+        match command {
+            "uci" => {
+                println!("id name Rob Chess");
+                println!("id author robbagott");
+                println!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_HASH_MB, MAX_HASH_MB
+                );
+                println!(
+                    "option name Threads type spin default 1 min 1 max {}",
+                    MAX_THREADS
+                );
+                println!("option name Clear Hash type button");
+                for name in SEARCH_OPTIONS.iter() {
+                    println!("option name {} type check default true", name);
+                }
+                println!("uciok");
+            }
+            "isready" => println!("readyok"),
+            "ucinewgame" => {
+                self.stop_search();
+                self.game = GameContext::new();
+                self.tt.clear();
+            }
+            "position" => {
+                self.stop_search();
+                match parse_position(&args) {
+                    Ok(g) => self.game = g,
+                    Err(err) => println!("info string {}", err),
+                }
+            }
+            "go" => {
+                self.stop_search();
+                self.go(parse_go(&args));
+            }
+            "stop" => self.stop_search(),
+            "setoption" => {
+                self.stop_search();
+                let (name, value) = parse_option(&args);
+                self.set_option(&name, value.as_deref());
+            }
+            "quit" => return false,
+            _ => println!("info string unknown command '{}'", command),
+        }
+        true
+    }
+
+    fn set_option(&mut self, name: &str, value: Option<&str>) {
+        if let Some(flag) = search_option(&mut self.options, name) {
+            match value.map(|value| value.parse::<bool>()) {
+                Some(Ok(value)) => *flag = value,
+                _ => println!(
+                    "info string invalid {} value '{}'",
+                    name,
+                    value.unwrap_or("")
+                ),
+            }
+            return;
+        }
+        match (name.to_lowercase().as_str(), value) {
+            ("hash", Some(value)) => match value.parse::<usize>() {
+                Ok(megabytes) => {
+                    self.tt = Arc::new(TranspositionTable::new(megabytes.clamp(1, MAX_HASH_MB)))
+                }
+                Err(_) => println!("info string invalid Hash value '{}'", value),
+            },
+            ("threads", Some(value)) => match value.parse::<usize>() {
+                Ok(threads) => self.options.threads = threads.clamp(1, MAX_THREADS),
+                Err(_) => println!("info string invalid Threads value '{}'", value),
+            },
+            ("clear hash", _) => self.tt.clear(),
+            _ => println!("info string unknown option '{}'", name),
+        }
+    }
+
+    // Searches on a separate thread so that stop and isready can be answered in the meantime.
+    fn go(&mut self, limits: SearchLimits) {
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut g = self.game.clone();
+        let options = self.options;
+        let search_stop = Arc::clone(&stop);
+        let tt = Arc::clone(&self.tt);
+        let handle = thread::spawn(move || {
+            // A search that fails still answers with a move, so the GUI isn't left waiting.
+            let best_move = panic::catch_unwind(AssertUnwindSafe(|| {
+                engine::search(&mut g, &tt, &limits, &options, &search_stop, |info| {
+                    println!("{}", format_info(info))
+                })
+            }))
+            .unwrap_or_else(|_| {
+                println!("info string the search failed");
+                None
+            });
+            // An infinite search must not report its move until told to stop.
+            while limits.infinite && !search_stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(10));
+            }
+            match best_move {
+                Some(m) => println!("bestmove {}", m),
+                None => println!("bestmove 0000"),
+            }
+        });
+        self.search = Some((stop, handle));
+    }
+
+    fn stop_search(&mut self) {
+        if let Some((stop, handle)) = self.search.take() {
+            stop.store(true, Ordering::Relaxed);
+            if handle.join().is_err() {
+                println!("info string the search failed");
+            }
+        }
+    }
+}
+
+// The search's selective techniques, which may be turned off to measure what each is worth.
+pub fn search_option<'a>(options: &'a mut SearchOptions, name: &str) -> Option<&'a mut bool> {
+    match name.to_lowercase().as_str() {
+        "nullmove" => Some(&mut options.null_move),
+        "latemovereductions" => Some(&mut options.late_move_reductions),
+        "reversefutility" => Some(&mut options.reverse_futility),
+        "futility" => Some(&mut options.futility),
+        "checkextensions" => Some(&mut options.check_extensions),
+        _ => None,
+    }
+}
+
+// Parses the arguments of 'position startpos|fen <fen> [moves <move>...]' into a game.
+pub fn parse_position(args: &[&str]) -> Result<GameContext, PositionError> {
+    let syntax_error = || PositionError::Syntax(args.join(" "));
+    let moves_at = args.iter().position(|&arg| arg == "moves");
+    let (setup, moves) = match moves_at {
+        Some(i) => (&args[..i], &args[i + 1..]),
+        None => (args, &args[args.len()..]),
+    };
+
+    let mut g = match setup.split_first() {
+        Some((&"startpos", [])) => GameContext::from_fen(START_FEN),
+        Some((&"fen", fen)) if !fen.is_empty() => GameContext::from_fen(&fen.join(" ")),
+        _ => return Err(syntax_error()),
+    }
+    .map_err(PositionError::InvalidFen)?;
+
+    for &m in moves {
+        let illegal = || PositionError::IllegalMove(m.to_owned());
+        let chess_move = ChessMove::from_algebraic(m, &g.position).map_err(|_| illegal())?;
+        g.make_move(chess_move).map_err(|_| illegal())?;
+    }
+    Ok(g)
+}
+
+// Parses the arguments of a go command. Unrecognized arguments are ignored.
+pub fn parse_go(args: &[&str]) -> SearchLimits {
+    let mut limits = SearchLimits::default();
+    let mut tokens = args.iter();
+    while let Some(&token) = tokens.next() {
+        let mut value = || tokens.next().and_then(|v| v.parse::<u64>().ok());
+        match token {
+            "depth" => limits.depth = value().map(|d| d as u32),
+            "nodes" => limits.nodes = value(),
+            "movetime" => limits.movetime = value(),
+            "wtime" => limits.wtime = value(),
+            "btime" => limits.btime = value(),
+            "winc" => limits.winc = value(),
+            "binc" => limits.binc = value(),
+            "movestogo" => limits.movestogo = value(),
+            "infinite" => limits.infinite = true,
+            _ => {}
+        }
+    }
+    limits
+}
+
+// Splits the arguments of 'setoption name <name> [value <value>]'. Names may contain spaces.
+pub fn parse_option(args: &[&str]) -> (String, Option<String>) {
+    let args = match args.split_first() {
+        Some((&"name", rest)) => rest,
+        _ => args,
+    };
+    match args.iter().position(|&arg| arg == "value") {
+        Some(i) => (args[..i].join(" "), Some(args[i + 1..].join(" "))),
+        None => (args.join(" "), None),
+    }
+}
+
+// Formats a score as UCI does, e.g. 'cp 125' or 'mate -3'.
+pub fn format_score(score: Score) -> String {
+    match score.mate_in_moves() {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", score.centipawns()),
+    }
+}
+
+pub fn format_info(info: &engine::SearchInfo) -> String {
+    let pv = info
+        .pv
+        .iter()
+        .map(|m| m.to_string())
+        .collect::<Vec<String>>();
+    format!(
+        "info depth {} score {} nodes {} time {} hashfull {} pv {}",
+        info.depth,
+        format_score(info.score),
+        info.nodes,
+        info.time.as_millis(),
+        info.hashfull,
+        pv.join(" ")
+    )
+}
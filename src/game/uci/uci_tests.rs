@@ -0,0 +1,137 @@
+use super::*;
+use crate::game::color::Color;
+use std::time::Duration;
+
+#[test]
+fn parse_position_startpos_with_moves() {
+    let g = parse_position(&["startpos", "moves", "e2e4", "e7e5", "g1f3"]).unwrap();
+    assert_eq!(g.chess_moves.len(), 3);
+    assert_eq!(
+        g.position.to_fen(),
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+    );
+}
+
+#[test]
+fn parse_position_fen_with_castling_and_promotion() {
+    let fen = "r3k3/6P1/8/8/8/8/8/R3K2R w KQq - 0 1";
+    let args: Vec<&str> = ["fen"]
+        .iter()
+        .copied()
+        .chain(fen.split(' '))
+        .chain(["moves", "e1g1", "e8c8", "g7g8q"].iter().copied())
+        .collect();
+    let g = parse_position(&args).unwrap();
+    assert_eq!(g.position.to_fen(), "2kr2Q1/8/8/8/8/8/8/R4RK1 b - - 0 2");
+}
+
+#[test]
+fn parse_position_errors() {
+    assert_eq!(
+        parse_position(&["startpos", "moves", "e2e5"]).unwrap_err(),
+        PositionError::IllegalMove("e2e5".to_owned())
+    );
+    assert!(matches!(
+        parse_position(&["fen", "8/8/8/8/8/8/8/8", "w", "-", "-"]),
+        Err(PositionError::InvalidFen(_))
+    ));
+    // The king of the side that just moved can't be left in check.
+    assert_eq!(
+        parse_position(&["fen", "4k3/8/8/8/8/8/8/4R1K1", "w", "-", "-"]).unwrap_err(),
+        PositionError::InvalidFen(FenError::OpponentInCheck(Color::Black))
+    );
+    assert!(matches!(
+        parse_position(&["somewhere"]),
+        Err(PositionError::Syntax(_))
+    ));
+}
+
+#[test]
+fn parse_go_arguments() {
+    let limits = parse_go(&[
+        "wtime",
+        "60000",
+        "btime",
+        "30000",
+        "winc",
+        "1000",
+        "binc",
+        "500",
+        "movestogo",
+        "20",
+    ]);
+    assert_eq!(
+        limits,
+        SearchLimits {
+            wtime: Some(60000),
+            btime: Some(30000),
+            winc: Some(1000),
+            binc: Some(500),
+            movestogo: Some(20),
+            ..SearchLimits::default()
+        }
+    );
+    assert_eq!(
+        limits.think_time(Color::White),
+        Some(Duration::from_millis(4000))
+    );
+    assert_eq!(
+        limits.think_time(Color::Black),
+        Some(Duration::from_millis(2000))
+    );
+
+    let limits = parse_go(&["depth", "4"]);
+    assert_eq!(limits.depth, Some(4));
+    assert_eq!(limits.think_time(Color::White), None);
+
+    let limits = parse_go(&["infinite"]);
+    assert!(limits.infinite);
+    assert_eq!(limits.think_time(Color::White), None);
+
+    let limits = parse_go(&["movetime", "250"]);
+    assert_eq!(
+        limits.think_time(Color::Black),
+        Some(Duration::from_millis(250))
+    );
+}
+
+#[test]
+fn think_time_keeps_a_reserve() {
+    let limits = parse_go(&["wtime", "40", "winc", "1000"]);
+    assert_eq!(limits.think_time(Color::White), Some(Duration::ZERO));
+}
+
+#[test]
+fn parse_option_names_and_values() {
+    assert_eq!(
+        parse_option(&["name", "Clear", "Hash"]),
+        ("Clear Hash".to_owned(), None)
+    );
+    assert_eq!(
+        parse_option(&["name", "Hash", "value", "64"]),
+        ("Hash".to_owned(), Some("64".to_owned()))
+    );
+}
+
+#[test]
+fn format_score_in_centipawns_or_mate() {
+    assert_eq!(format_score(Score::cp(-35)), "cp -35");
+    assert_eq!(format_score(-Score::mated_in(3)), "mate 2");
+    assert_eq!(format_score(Score::mated_in(4)), "mate -2");
+}
+
+#[test]
+fn search_options_are_found_by_name() {
+    let mut options = SearchOptions::default();
+    *search_option(&mut options, "NullMove").unwrap() = false;
+    *search_option(&mut options, "checkextensions").unwrap() = false;
+    assert_eq!(
+        options,
+        SearchOptions {
+            null_move: false,
+            check_extensions: false,
+            ..SearchOptions::default()
+        }
+    );
+    assert_eq!(search_option(&mut options, "Hash"), None);
+}
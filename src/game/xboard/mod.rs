@@ -0,0 +1,400 @@
+#[cfg(test)]
+mod xboard_tests;
+
+use super::chess_move::ChessMove;
+use super::color::Color;
+use super::engine;
+use super::engine::limits::SearchLimits;
+use super::engine::options::{SearchOptions, MAX_THREADS};
+use super::engine::score::Score;
+use super::engine::transposition::{TranspositionTable, MAX_HASH_MB};
+use super::game_context::GameContext;
+use std::io::{self, BufRead};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+// Both the GUI and finished searches are heard from through a single channel, so that the game
+// is only ever changed from the thread reading it.
+enum Event {
+    Command(String),
+    // A search has finished. Searches are numbered so that one that was abandoned can be ignored.
+    SearchDone(u32),
+}
+
+// A time control set by the level command. Times are in milliseconds.
+#[derive(Debug, PartialEq)]
+pub struct Level {
+    pub moves_per_session: u64,
+    pub base: u64,
+    pub increment: u64,
+}
+
+struct XBoard {
+    game: GameContext,
+    // Shared with the search thread. Resizing replaces it, leaving any search with the old one.
+    tt: Arc<TranspositionTable>,
+    // The side the engine plays, or None in force mode.
+    engine_color: Option<Color>,
+    post: bool,
+    max_depth: Option<u32>,
+    // Seconds per move as set by st.
+    move_time: Option<u64>,
+    level: Option<Level>,
+    // The engine's remaining time in milliseconds as last told by the time command.
+    clock: Option<u64>,
+    // How many threads each search uses, as set by cores.
+    threads: usize,
+    searches: u32,
+    search: Option<(Arc<AtomicBool>, JoinHandle<Option<ChessMove>>)>,
+    events: Sender<Event>,
+}
+
+pub fn start_xboard_session() {
+    let (events, receiver) = mpsc::channel();
+    let stdin_events = events.clone();
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            match line {
+                Ok(line) => {
+                    if stdin_events.send(Event::Command(line)).is_err() {
+                        return;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+        // Treat the GUI going away as being asked to quit.
+        let _ = stdin_events.send(Event::Command("quit".to_owned()));
+    });
+
+    let mut xboard = XBoard::new(events);
+    run(&mut xboard, &receiver);
+    xboard.stop_search();
+}
+
+fn run(xboard: &mut XBoard, receiver: &Receiver<Event>) {
+    for event in receiver.iter() {
+        let keep_going = match event {
+            Event::Command(line) => xboard.handle(&line),
+            Event::SearchDone(id) => {
+                xboard.finish_search(id);
+                true
+            }
+        };
+        if !keep_going {
+            break;
+        }
+    }
+}
+
+impl XBoard {
+    fn new(events: Sender<Event>) -> XBoard {
+        XBoard {
+            game: GameContext::new(),
+            tt: Arc::new(TranspositionTable::default()),
+            engine_color: Some(Color::Black),
+            post: false,
+            max_depth: None,
+            move_time: None,
+            level: None,
+            clock: None,
+            threads: 1,
+            searches: 0,
+            search: None,
+            events,
+        }
+    }
+
+    // Responds to a single command from the GUI. Returns false when the session should end.
+    fn handle(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+        let command = match tokens.next() {
+            Some(command) => command,
+            None => return true,
+        };
+        let args: Vec<&str> = tokens.collect();
+        match command {
+            "protover" => println!(
+                "feature myname=\"Rob Chess\" usermove=1 setboard=1 ping=1 memory=1 smp=1 colors=0 sigint=0 sigterm=0 analyze=0 done=1"
+            ),
+            "new" => {
+                self.abandon_search();
+                self.game = GameContext::new();
+                self.clear_tt();
+                self.engine_color = Some(Color::Black);
+                self.max_depth = None;
+                self.clock = None;
+            }
+            "force" => {
+                self.abandon_search();
+                self.engine_color = None;
+            }
+            "go" => {
+                self.abandon_search();
+                self.engine_color = Some(self.game.position.side_to_move);
+                self.start_search();
+            }
+            "playother" => {
+                self.abandon_search();
+                self.engine_color = Some(self.game.position.side_to_move.opp_color());
+            }
+            "usermove" => match args.first() {
+                Some(m) => {
+                    self.abandon_search();
+                    self.user_move(m)
+                }
+                None => println!("Error (no move given): usermove"),
+            },
+            "level" => match parse_level(&args) {
+                Some(level) => self.level = Some(level),
+                None => println!("Error (bad time control): {}", line),
+            },
+            "memory" => match args.first().and_then(|mb| mb.parse::<usize>().ok()) {
+                Some(megabytes) => {
+                    self.abandon_search();
+                    self.tt = Arc::new(TranspositionTable::new(megabytes.clamp(1, MAX_HASH_MB)));
+                }
+                None => println!("Error (bad memory size): {}", line),
+            },
+            "cores" => match args.first().and_then(|n| n.parse::<usize>().ok()) {
+                Some(cores) => self.threads = cores.clamp(1, MAX_THREADS),
+                None => println!("Error (bad number of cores): {}", line),
+            },
+            "st" => self.move_time = args.first().and_then(|s| s.parse().ok()),
+            "sd" => self.max_depth = args.first().and_then(|d| d.parse().ok()),
+            "time" => {
+                self.clock = args
+                    .first()
+                    .and_then(|cs| cs.parse::<u64>().ok())
+                    .map(|cs| cs * 10)
+            }
+            "setboard" => {
+                self.abandon_search();
+                match GameContext::from_fen(&args.join(" ")) {
+                    Ok(g) => self.game = g,
+                    Err(err) => println!("tellusererror Illegal position: {}", err),
+                }
+            }
+            "undo" => {
+                self.abandon_search();
+                self.take_back(command, 1);
+            }
+            "remove" => {
+                self.abandon_search();
+                self.take_back(command, 2);
+            }
+            "result" => {
+                self.abandon_search();
+                self.engine_color = None;
+            }
+            "?" => {
+                // Ask the search to stop. Its move is played once it has finished.
+                if let Some((stop, _)) = &self.search {
+                    stop.store(true, Ordering::Relaxed);
+                }
+            }
+            "ping" => println!("pong {}", args.first().unwrap_or(&"")),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "quit" => return false,
+            "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer"
+            | "otim" | "name" | "rating" => {}
+            _ => println!("Error (unknown command): {}", command),
+        }
+        true
+    }
+
+    fn user_move(&mut self, m: &str) {
+        let chess_move = match ChessMove::from_algebraic(m, &self.game.position) {
+            Ok(chess_move) => chess_move,
+            Err(_) => {
+                println!("Illegal move: {}", m);
+                return;
+            }
+        };
+        if self.game.make_move(chess_move).is_err() {
+            println!("Illegal move: {}", m);
+            return;
+        }
+        if !self.report_result() && self.engine_color == Some(self.game.position.side_to_move) {
+            self.start_search();
+        }
+    }
+
+    // Takes back moves for the undo or remove command.
+    fn take_back(&mut self, command: &str, moves: usize) {
+        for _ in 0..moves {
+            if self.game.undo_move().is_err() {
+                println!("Error (no moves to take back): {}", command);
+                return;
+            }
+        }
+    }
+
+    // Announces the end of the game if it is over. Returns whether it was.
+    fn report_result(&self) -> bool {
+        if !self.game.status.is_over() {
+            return false;
+        }
+        println!("{} {{{}}}", self.game.result(), self.game.status);
+        true
+    }
+
+    // The limits of the next search, from the time control and the clock.
+    fn search_limits(&self) -> SearchLimits {
+        let color = self.game.position.side_to_move;
+        // Before the GUI reports the clock, the whole of the base time is left.
+        let clock = self.clock.or(self.level.as_ref().map(|level| level.base));
+        let increment = self.level.as_ref().map(|level| level.increment);
+        let moves_to_go = self
+            .level
+            .as_ref()
+            .filter(|level| level.moves_per_session > 0)
+            .map(|level| {
+                let played = u64::from(self.game.position.fullmove_number - 1);
+                level.moves_per_session - played % level.moves_per_session
+            });
+        let mut limits = SearchLimits {
+            depth: self.max_depth,
+            movetime: self.move_time.map(|seconds| seconds * 1000),
+            movestogo: moves_to_go,
+            ..SearchLimits::default()
+        };
+        match color {
+            Color::White => {
+                limits.wtime = clock;
+                limits.winc = increment;
+            }
+            Color::Black => {
+                limits.btime = clock;
+                limits.binc = increment;
+            }
+        }
+        limits
+    }
+
+    fn start_search(&mut self) {
+        let limits = self.search_limits();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        self.searches += 1;
+        let id = self.searches;
+        let mut g = self.game.clone();
+        let post = self.post;
+        let search_stop = Arc::clone(&stop);
+        let events = self.events.clone();
+        let tt = Arc::clone(&self.tt);
+        let options = SearchOptions {
+            threads: self.threads,
+            ..SearchOptions::default()
+        };
+        let handle = thread::spawn(move || {
+            let best_move = panic::catch_unwind(AssertUnwindSafe(|| {
+                engine::search(&mut g, &tt, &limits, &options, &search_stop, |info| {
+                    if post {
+                        println!("{}", format_thinking(info));
+                    }
+                })
+            }));
+            // The session hears of a failed search too, and reports it when joining the thread.
+            let _ = events.send(Event::SearchDone(id));
+            best_move.unwrap_or_else(|payload| panic::resume_unwind(payload))
+        });
+        self.search = Some((stop, handle));
+    }
+
+    // Plays the move found by the search, unless the search has since been abandoned.
+    fn finish_search(&mut self, id: u32) {
+        if id != self.searches {
+            return;
+        }
+        let best_move = match self.search.take().map(|(_, handle)| handle.join()) {
+            Some(Ok(best_move)) => best_move,
+            Some(Err(_)) => {
+                println!("tellusererror The search failed");
+                self.engine_color = None;
+                return;
+            }
+            None => return,
+        };
+        if let Some(chess_move) = best_move {
+            self.game
+                .make_move(chess_move)
+                .expect("The engine chose an illegal move");
+            println!("move {}", chess_move);
+        }
+        self.report_result();
+    }
+
+    fn clear_tt(&self) {
+        self.tt.clear();
+    }
+
+    // Stops any search in progress and discards its move.
+    fn abandon_search(&mut self) {
+        self.stop_search();
+        self.searches += 1;
+    }
+
+    fn stop_search(&mut self) {
+        if let Some((stop, handle)) = self.search.take() {
+            stop.store(true, Ordering::Relaxed);
+            if handle.join().is_err() {
+                println!("tellusererror The search failed");
+            }
+        }
+    }
+}
+
+// Parses the arguments of 'level <moves per session> <base> <increment>', where the base is in
+// minutes or minutes:seconds and the increment in seconds.
+pub fn parse_level(args: &[&str]) -> Option<Level> {
+    if args.len() != 3 {
+        return None;
+    }
+    let moves_per_session = args[0].parse().ok()?;
+    let base = match args[1].split_once(':') {
+        Some((minutes, seconds)) => {
+            minutes.parse::<u64>().ok()? * 60_000 + seconds.parse::<u64>().ok()? * 1000
+        }
+        None => args[1].parse::<u64>().ok()? * 60_000,
+    };
+    let increment = (args[2].parse::<f64>().ok()? * 1000.0) as u64;
+    Some(Level {
+        moves_per_session,
+        base,
+        increment,
+    })
+}
+
+// Formats a score as xboard does, in centipawns, with mate in n moves as 100000 + n and being
+// mated in n as -100000 - n.
+pub fn format_score(score: Score) -> i32 {
+    match score.mate_in_moves() {
+        Some(moves) if moves > 0 => 100_000 + moves,
+        Some(moves) => -100_000 + moves,
+        None => score.centipawns(),
+    }
+}
+
+// Formats a completed depth as xboard thinking output: ply, score in centipawns, time in
+// centiseconds, nodes and the principal variation.
+pub fn format_thinking(info: &engine::SearchInfo) -> String {
+    let pv = info
+        .pv
+        .iter()
+        .map(|m| m.to_string())
+        .collect::<Vec<String>>();
+    format!(
+        "{} {} {} {} {}",
+        info.depth,
+        format_score(info.score),
+        info.time.as_millis() / 10,
+        info.nodes,
+        pv.join(" ")
+    )
+}
@@ -0,0 +1,145 @@
+use super::*;
+use crate::game::position::START_FEN;
+
+fn xboard() -> (XBoard, Receiver<Event>) {
+    let (events, receiver) = mpsc::channel();
+    (XBoard::new(events), receiver)
+}
+
+// Waits for the engine's search to finish and plays its move, as the session loop would.
+fn wait_for_move(x: &mut XBoard, receiver: &Receiver<Event>) {
+    match receiver.recv().unwrap() {
+        Event::SearchDone(id) => x.finish_search(id),
+        Event::Command(_) => panic!("Expected the search to finish"),
+    }
+}
+
+#[test]
+fn engine_replies_to_user_moves() {
+    let (mut x, receiver) = xboard();
+    x.handle("new");
+    x.handle("sd 1");
+    x.handle("usermove e2e4");
+    wait_for_move(&mut x, &receiver);
+    assert_eq!(x.game.chess_moves.len(), 2);
+    assert_eq!(x.game.position.side_to_move, Color::White);
+}
+
+#[test]
+fn cores_sets_the_search_threads() {
+    let (mut x, receiver) = xboard();
+    x.handle("cores 4");
+    assert_eq!(x.threads, 4);
+    x.handle("cores 0");
+    assert_eq!(x.threads, 1);
+    x.handle("sd 2");
+    x.handle("cores 3");
+    x.handle("usermove e2e4");
+    wait_for_move(&mut x, &receiver);
+    assert_eq!(x.game.chess_moves.len(), 2);
+}
+
+#[test]
+fn force_mode_only_records_moves() {
+    let (mut x, _receiver) = xboard();
+    x.handle("force");
+    x.handle("usermove e2e4");
+    x.handle("usermove e7e5");
+    x.handle("usermove e1e3");
+    assert_eq!(x.game.chess_moves.len(), 2);
+    assert!(x.search.is_none());
+}
+
+#[test]
+fn go_plays_the_side_to_move() {
+    let (mut x, receiver) = xboard();
+    x.handle("force");
+    x.handle("sd 1");
+    x.handle("usermove e2e4");
+    x.handle("go");
+    wait_for_move(&mut x, &receiver);
+    assert_eq!(x.engine_color, Some(Color::Black));
+    assert_eq!(x.game.position.side_to_move, Color::White);
+}
+
+#[test]
+fn undo_and_remove_take_back_moves() {
+    let (mut x, _receiver) = xboard();
+    x.handle("force");
+    for m in &["usermove e2e4", "usermove e7e5", "usermove g1f3"] {
+        x.handle(m);
+    }
+    x.handle("undo");
+    assert_eq!(
+        x.game.position.to_fen(),
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+    );
+    x.handle("remove");
+    assert_eq!(x.game.position.to_fen(), START_FEN);
+    assert!(x.game.chess_moves.is_empty());
+}
+
+#[test]
+fn setboard_replaces_the_game() {
+    let (mut x, _receiver) = xboard();
+    x.handle("force");
+    x.handle("setboard 4k3/8/8/8/8/8/4P3/4K3 b - - 0 1");
+    assert_eq!(x.game.position.to_fen(), "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1");
+    x.handle("setboard not a position");
+    assert_eq!(x.game.position.to_fen(), "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1");
+    x.handle("setboard 4k3/8/8/8/8/8/8/4R1K1 w - - 0 1");
+    assert_eq!(x.game.position.to_fen(), "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1");
+}
+
+#[test]
+fn result_ends_the_engines_play() {
+    let (mut x, _receiver) = xboard();
+    x.handle("result 1-0 {White resigns}");
+    x.handle("usermove e2e4");
+    assert_eq!(x.engine_color, None);
+    assert!(x.search.is_none());
+}
+
+#[test]
+fn parse_level_time_controls() {
+    assert_eq!(
+        parse_level(&["40", "5", "0"]),
+        Some(Level {
+            moves_per_session: 40,
+            base: 300_000,
+            increment: 0
+        })
+    );
+    assert_eq!(
+        parse_level(&["0", "2:30", "1.5"]),
+        Some(Level {
+            moves_per_session: 0,
+            base: 150_000,
+            increment: 1500
+        })
+    );
+    assert_eq!(parse_level(&["0", "5"]), None);
+}
+
+#[test]
+fn time_controls_become_search_limits() {
+    let (mut x, _receiver) = xboard();
+    x.handle("level 40 5 0");
+    x.handle("time 6000");
+    let limits = x.search_limits();
+    assert_eq!(limits.wtime, Some(60_000));
+    assert_eq!(limits.movestogo, Some(40));
+
+    x.handle("st 3");
+    x.handle("sd 4");
+    let limits = x.search_limits();
+    assert_eq!(limits.movetime, Some(3000));
+    assert_eq!(limits.depth, Some(4));
+}
+
+#[test]
+fn format_score_marks_mates() {
+    assert_eq!(format_score(Score::cp(125)), 125);
+    assert_eq!(format_score(-Score::mated_in(5)), 100_003);
+    assert_eq!(format_score(Score::mated_in(2)), -100_001);
+}
@@ -0,0 +1,5 @@
+// Moves and positions report failures with Result<_, ()> where there is nothing to describe beyond
+// the failure itself.
+#![allow(clippy::result_unit_err)]
+
+pub mod game;
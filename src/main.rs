@@ -1,5 +1,33 @@
-mod game;
+use rob_chess::game;
+use rob_chess::game::position::START_FEN;
+use std::env;
+use std::process;
 
 fn main() {
-    game::start_user_session();
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("--uci") => game::uci::start_uci_session(),
+        Some("--xboard") => game::xboard::start_xboard_session(),
+        Some("perft") => perft(&args[1..]),
+        _ => game::start_user_session(),
+    }
+}
+
+// Runs 'perft <depth> [fen]', defaulting to the standard starting position.
+fn perft(args: &[String]) {
+    let depth = match args.first().and_then(|d| d.parse::<u32>().ok()) {
+        Some(depth) => depth,
+        None => {
+            eprintln!("Usage: rob_chess perft <depth> [fen]");
+            process::exit(2);
+        }
+    };
+    let fen = match args[1..].join(" ") {
+        fen if fen.is_empty() => START_FEN.to_owned(),
+        fen => fen,
+    };
+    if let Err(err) = game::run_perft(depth, &fen) {
+        eprintln!("The FEN could not be read: {}", err);
+        process::exit(2);
+    }
 }
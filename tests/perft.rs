@@ -0,0 +1,73 @@
+// Move generation is checked against the standard perft positions, whose counts are agreed on
+// by many engines. The deeper counts take too long for a debug build, so they are ignored by
+// default and can be run with `cargo test --release -- --ignored`.
+
+use rob_chess::game::position::{Position, START_FEN};
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+const POSITION_6: &str = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+// Checks the perft counts of a position from depth 1 up to the number of counts given.
+fn assert_perft(fen: &str, expected: &[u64]) {
+    let mut p = Position::from_fen(fen).unwrap();
+    for (i, &nodes) in expected.iter().enumerate() {
+        let depth = i as u32 + 1;
+        assert_eq!(p.perft(depth), nodes, "perft({}) of {}", depth, fen);
+    }
+    // Counting must leave the position as it was.
+    assert_eq!(p.to_fen(), fen);
+}
+
+#[test]
+fn perft_start_position() {
+    assert_perft(START_FEN, &[20, 400, 8_902, 197_281]);
+}
+
+#[test]
+fn perft_kiwipete() {
+    assert_perft(KIWIPETE, &[48, 2_039, 97_862]);
+}
+
+#[test]
+fn perft_position_3() {
+    assert_perft(POSITION_3, &[14, 191, 2_812, 43_238]);
+}
+
+#[test]
+fn perft_position_4() {
+    assert_perft(POSITION_4, &[6, 264, 9_467]);
+}
+
+#[test]
+fn perft_position_5() {
+    assert_perft(POSITION_5, &[44, 1_486, 62_379]);
+}
+
+#[test]
+fn perft_position_6() {
+    assert_perft(POSITION_6, &[46, 2_079, 89_890]);
+}
+
+#[test]
+#[ignore]
+fn perft_deep() {
+    assert_perft(START_FEN, &[20, 400, 8_902, 197_281, 4_865_609]);
+    assert_perft(KIWIPETE, &[48, 2_039, 97_862, 4_085_603]);
+    assert_perft(POSITION_3, &[14, 191, 2_812, 43_238, 674_624]);
+    assert_perft(POSITION_4, &[6, 264, 9_467, 422_333]);
+    assert_perft(POSITION_5, &[44, 1_486, 62_379, 2_103_487]);
+    assert_perft(POSITION_6, &[46, 2_079, 89_890, 3_894_594]);
+}
+
+#[test]
+fn divide_adds_up_to_perft() {
+    let mut p = Position::from_fen(KIWIPETE).unwrap();
+    let divided = p.divide(2);
+    assert_eq!(divided.len(), 48);
+    assert_eq!(divided.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2_039);
+    let castle = divided.iter().find(|(m, _)| m.to_string() == "e1g1");
+    assert_eq!(castle.map(|(_, nodes)| *nodes), Some(43));
+}